@@ -5,6 +5,20 @@ const FILE_ITEMS: &str = "Breath of the Wild Items.txt";
 use botw::*;
 
 pub fn main() {
+    // "botw lint [directory]" checks the catalog files and exits with a nonzero status if there
+    // are any findings.
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(|x| x.as_str()) == Some("lint") {
+        let finding_count = model_3::validate::main(args.get(2).map(|x| x.as_str()));
+        std::process::exit(if finding_count == 0 { 0 } else { 1 });
+    }
+    // "botw convert [from directory] [to directory]" writes the structured catalog from the text
+    // catalog files.
+    if args.get(1).map(|x| x.as_str()) == Some("convert") {
+        let converted = model_3::catalog::main(args.get(2).map(|x| x.as_str()), args.get(3).map(|x| x.as_str()));
+        std::process::exit(if converted { 0 } else { 1 });
+    }
+
     println!("\nBotW start\n");
 
     // model_3::model::main();
@@ -0,0 +1,112 @@
+use std::collections::BTreeSet;
+
+use crate::*;
+
+// The lists in the model that an alias can point into. These are also the section headers in the
+// alias files, as in "#Locations".
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum AliasList {
+    Character,
+    Location,
+    Quest,
+    Item,
+}
+
+// Other names for characters, locations, quests, and items: the datamined internal names like
+// "Location_AkkareZhai", common nicknames like "Lynel gear", and any aliases the player has added.
+// An alias may point to more than one entry in the same list.
+#[derive(Debug, Default)]
+pub struct AliasIndex {
+    // Keyed by the list and the lowercase alias.
+    aliases: BTreeMap<(AliasList, String), AliasEntry>,
+}
+
+#[derive(Debug)]
+struct AliasEntry {
+    // The alias as it was first written, so that it can be written back the same way.
+    alias: String,
+    // The lowercase keys of the entries in the list.
+    keys: Vec<String>,
+}
+
+impl AliasList {
+    pub fn from_section_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "Characters" => Some(AliasList::Character),
+            "Locations" => Some(AliasList::Location),
+            "Quests" => Some(AliasList::Quest),
+            "Items" => Some(AliasList::Item),
+            _ => None,
+        }
+    }
+
+    pub fn section_name<'a>(&self) -> &'a str {
+        match self {
+            AliasList::Character => "Characters",
+            AliasList::Location => "Locations",
+            AliasList::Quest => "Quests",
+            AliasList::Item => "Items",
+        }
+    }
+
+    pub fn variant_to_string<'a>(&self) -> &'a str {
+        match self {
+            AliasList::Character => "Character",
+            AliasList::Location => "Location",
+            AliasList::Quest => "Quest",
+            AliasList::Item => "Item",
+        }
+    }
+}
+
+impl AliasIndex {
+    pub fn add(&mut self, list: AliasList, alias: &str, name: &str) {
+        let entry = self.aliases
+            .entry((list, alias.trim().to_lowercase()))
+            .or_insert_with(|| AliasEntry { alias: alias.trim().to_string(), keys: vec![] });
+        let key = name.trim().to_lowercase();
+        if !entry.keys.contains(&key) {
+            entry.keys.push(key);
+        }
+    }
+
+    // The keys of the entries that the alias points to, if any.
+    pub fn get(&self, list: AliasList, alias: &str) -> &[String] {
+        self.aliases.get(&(list, alias.trim().to_lowercase())).map_or(&[], |entry| entry.keys.as_slice())
+    }
+
+    // The keys of the entries that have an alias containing the partial name, for the kind of
+    // partial matching done in the shell.
+    pub fn keys_containing(&self, list: AliasList, partial_name: &str) -> BTreeSet<String> {
+        let partial_name = partial_name.trim().to_lowercase();
+        self.aliases
+            .iter()
+            .filter(|((alias_list, alias), _)| *alias_list == list && alias.contains(&partial_name))
+            .flat_map(|(_, entry)| entry.keys.iter().cloned())
+            .collect()
+    }
+
+    // The aliases that point to the entry with this key.
+    pub fn aliases_for(&self, list: AliasList, key: &str) -> Vec<String> {
+        let key = key.trim().to_lowercase();
+        self.aliases
+            .iter()
+            .filter(|((alias_list, _), entry)| *alias_list == list && entry.keys.contains(&key))
+            .map(|(_, entry)| entry.alias.clone())
+            .collect()
+    }
+
+    // Each alias as it was written, with its list and the keys of the entries it points to, ordered
+    // by list and then by alias ignoring case.
+    pub fn iter(&self) -> impl Iterator<Item = (AliasList, &String, &Vec<String>)> {
+        self.aliases.iter().map(|((list, _), entry)| (*list, &entry.alias, &entry.keys))
+    }
+
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
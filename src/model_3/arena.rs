@@ -0,0 +1,132 @@
+use std::marker::PhantomData;
+
+use crate::*;
+
+// Compact IDs for the entries in the model's lists. An ID is the entry's position in its arena, so
+// following a reference like a location's parent is an index rather than a lowercase string
+// lookup. The separate types keep a quest ID from being used to look up a location.
+pub trait EntityId: Copy + Ord {
+    fn from_index(index: usize) -> Self;
+    fn index(&self) -> usize;
+}
+
+macro_rules! entity_id {
+    ($name:ident) => {
+        #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        pub struct $name(u32);
+
+        impl EntityId for $name {
+            fn from_index(index: usize) -> Self {
+                assert!(index <= u32::MAX as usize, "Too many entries for {}.", stringify!($name));
+                $name(index as u32)
+            }
+
+            fn index(&self) -> usize {
+                self.0 as usize
+            }
+        }
+    };
+}
+
+entity_id!(CharacterId);
+entity_id!(LocationId);
+entity_id!(QuestId);
+entity_id!(ItemId);
+
+// The entries of one list in the order they were added, with each name interned once as a
+// lowercase key. Lookups by key and iteration by key work the same way as the BTreeMap<String, _>
+// that the lists used to be, while IDs give direct access.
+#[derive(Debug)]
+pub struct Arena<I: EntityId, T> {
+    entries: Vec<T>,
+    ids_by_key: BTreeMap<String, I>,
+    id_type: PhantomData<I>,
+}
+
+impl<I: EntityId, T> Default for Arena<I, T> {
+    fn default() -> Self {
+        Self {
+            entries: vec![],
+            ids_by_key: BTreeMap::new(),
+            id_type: PhantomData,
+        }
+    }
+}
+
+impl<I: EntityId, T> Arena<I, T> {
+    // Adds an entry under a key that isn't already in use and returns its new ID.
+    pub fn insert(&mut self, key: String, entry: T) -> I {
+        assert!(!self.ids_by_key.contains_key(&key), "Key {} already exists.", key);
+        let id = I::from_index(self.entries.len());
+        self.entries.push(entry);
+        self.ids_by_key.insert(key, id);
+        id
+    }
+
+    pub fn id(&self, key: &str) -> Option<I> {
+        self.ids_by_key.get(key).copied()
+    }
+
+    // The caller is expected to have gotten the ID from this arena.
+    pub fn by_id(&self, id: I) -> &T {
+        &self.entries[id.index()]
+    }
+
+    pub fn by_id_mut(&mut self, id: I) -> &mut T {
+        &mut self.entries[id.index()]
+    }
+
+    // For an ID that may have come from somewhere else, such as another model.
+    pub fn get_by_id(&self, id: I) -> Option<&T> {
+        self.entries.get(id.index())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&T> {
+        self.id(key).map(|id| self.by_id(id))
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut T> {
+        match self.id(key) {
+            Some(id) => Some(self.by_id_mut(id)),
+            None => None,
+        }
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.ids_by_key.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // The keys, IDs, and entries below are all in key order.
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.ids_by_key.keys()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = I> + '_ {
+        self.ids_by_key.values().copied()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.ids_by_key.values().map(move |id| &self.entries[id.index()])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &T)> {
+        self.ids_by_key.iter().map(move |(key, id)| (key, &self.entries[id.index()]))
+    }
+
+    pub fn iter_ids(&self) -> impl Iterator<Item = (I, &T)> {
+        self.ids_by_key.values().map(move |id| (*id, &self.entries[id.index()]))
+    }
+
+    // In the order the entries were added rather than key order.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.entries.iter_mut()
+    }
+}
@@ -0,0 +1,584 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::alias::AliasList;
+use super::data_source::{self, DataSource};
+use super::model::*;
+use super::parse::{self, MapLocationEntry, ParseError};
+
+// The structured alternative to the text catalog files. Everything is in one JSON file with a
+// version number so that the schema can grow. Fields that were added after the first version
+// have defaults so older files still load.
+pub const FILE_NAME_CATALOG: &str = "Breath of the Wild Catalog.json";
+pub const CATALOG_VERSION: u32 = 1;
+// If this environment variable is "text" or "json" it says which catalog format to load. Otherwise
+// the structured catalog is loaded if there is one, as long as none of the text files have been
+// changed since it was written.
+pub const ENV_CATALOG_FORMAT: &str = "BOTW_CATALOG_FORMAT";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CatalogFormat {
+    Text,
+    Structured,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Catalog {
+    pub version: u32,
+    pub characters: Vec<CharacterEntry>,
+    pub locations: Vec<LocationEntry>,
+    pub quests: Vec<QuestEntry>,
+    // In Hyrule Compendium order.
+    pub items: Vec<ItemEntry>,
+    #[serde(default)]
+    pub armor_sets: Vec<ArmorSetEntry>,
+    // Every entry in the datamined map file, including the ones that aren't joined to a location.
+    #[serde(default)]
+    pub map_entries: Vec<MapLocationEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CharacterEntry {
+    pub name: String,
+    pub race: Race,
+    #[serde(default)]
+    pub main: bool,
+    #[serde(default)]
+    pub champion: bool,
+    #[serde(default)]
+    pub merchant: bool,
+    #[serde(default)]
+    pub dead: bool,
+    #[serde(default)]
+    pub content_pack: ContentPack,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LocationEntry {
+    pub name: String,
+    pub typ: LocationType,
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub content_pack: ContentPack,
+    #[serde(default)]
+    pub internal_name: Option<String>,
+    #[serde(default)]
+    pub map_point: Option<MapPoint>,
+    #[serde(default)]
+    pub dog_treasure: Option<String>,
+    #[serde(default)]
+    pub challenge: Option<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct QuestEntry {
+    pub name: String,
+    pub typ: QuestType,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub shrine: Option<String>,
+    #[serde(default)]
+    pub content_pack: ContentPack,
+    #[serde(default)]
+    pub giver: Option<String>,
+    #[serde(default)]
+    pub start_location: Option<String>,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ItemEntry {
+    pub name: String,
+    pub typ: ItemType,
+    #[serde(default)]
+    pub effect: Option<Effect>,
+    #[serde(default)]
+    pub effect_description: Option<String>,
+    #[serde(default)]
+    pub is_monster_part: bool,
+    #[serde(default)]
+    pub mon_sell_price: Option<usize>,
+    #[serde(default)]
+    pub purchase_price: Option<Price>,
+    #[serde(default)]
+    pub upgrade_prices: Vec<Price>,
+    #[serde(default)]
+    pub recipe: Option<RecipeEntry>,
+    #[serde(default)]
+    pub content_pack: ContentPack,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ArmorSetEntry {
+    pub name: String,
+    #[serde(default)]
+    pub set_bonus: Option<String>,
+    #[serde(default)]
+    pub head: Option<String>,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub legs: Option<String>,
+    #[serde(default)]
+    pub shop_locations: Vec<String>,
+    #[serde(default)]
+    pub content_pack: ContentPack,
+}
+
+// The recipe for a food item. Each ingredient is a list of alternatives.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RecipeEntry {
+    pub typ: RecipeType,
+    #[serde(default)]
+    pub level: Option<usize>,
+    #[serde(default)]
+    pub ingredients: Vec<Vec<String>>,
+    #[serde(default)]
+    pub method: Option<String>,
+}
+
+// "botw convert [from directory] [to directory]" reads the text catalog files and writes the
+// structured catalog.
+pub fn main(from_path: Option<&str>, to_path: Option<&str>) -> bool {
+    let source = from_path.map_or_else(DataSource::new_default, DataSource::new_directory);
+    let to_path = to_path.unwrap_or(".");
+    println!("Converting {}.", source.description());
+    let model = match Model::load_text(&source, &ContentOptions::new_all()) {
+        Ok(model) => model,
+        Err(errors) => {
+            parse::print_errors(&errors);
+            return false;
+        }
+    };
+    match write_catalog(&model, to_path) {
+        Ok(()) => {
+            println!("Wrote \"{}\" to \"{}\".", FILE_NAME_CATALOG, to_path);
+            true
+        },
+        Err(e) => {
+            println!("Couldn't write \"{}\" to \"{}\": {}", FILE_NAME_CATALOG, to_path, e);
+            false
+        }
+    }
+}
+
+pub fn write_catalog<P: AsRef<Path>>(model: &Model, path: P) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(&Catalog::from_model(model)).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(path.as_ref().join(FILE_NAME_CATALOG), json)
+}
+
+pub fn load_model(source: &DataSource, content_options: &ContentOptions) -> Result<Model, Vec<ParseError>> {
+    let file_name = FILE_NAME_CATALOG;
+    let json = parse::read_file(source, file_name)?;
+    let catalog: Catalog = serde_json::from_str(&json)
+        .map_err(|e| vec![ParseError::new(file_name, Some(e.line()), &e.to_string(), "a catalog in the structured format")])?;
+    catalog.to_model(content_options)
+}
+
+// Which format to load from the source when the environment variable doesn't say. Loading the
+// structured catalog when the text files are newer would quietly drop whatever was changed in
+// them, so that's an error until one of the formats is chosen or the catalog is converted again.
+pub fn choose_format(source: &DataSource) -> Result<CatalogFormat, ParseError> {
+    if !source.has_file(FILE_NAME_CATALOG) {
+        return Ok(CatalogFormat::Text);
+    }
+    let catalog_modified = source.modified(FILE_NAME_CATALOG);
+    let newer_file_name = data_source::text_file_names()
+        .find(|file_name| match (source.modified(file_name), catalog_modified) {
+            (Some(modified), Some(catalog_modified)) => modified > catalog_modified,
+            _ => false,
+        });
+    match newer_file_name {
+        Some(file_name) => Err(ParseError::new(
+            FILE_NAME_CATALOG,
+            None,
+            &format!("\"{}\" was changed after the catalog was written", file_name),
+            &format!("a catalog at least as new as the text files, or {} set to \"text\" or \"json\"", ENV_CATALOG_FORMAT))),
+        None => Ok(CatalogFormat::Structured),
+    }
+}
+
+impl CatalogFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "text" => Some(CatalogFormat::Text),
+            "json" => Some(CatalogFormat::Structured),
+            _ => None,
+        }
+    }
+
+    pub fn from_env() -> Result<Option<Self>, ParseError> {
+        match env::var(ENV_CATALOG_FORMAT) {
+            Ok(name) => Self::from_name(&name)
+                .map(Some)
+                .ok_or_else(|| ParseError::new(ENV_CATALOG_FORMAT, None, &name, "\"text\" or \"json\"")),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+impl Catalog {
+    pub fn from_model(model: &Model) -> Self {
+        let characters = model.characters
+            .iter()
+            .map(|(key, character)| CharacterEntry {
+                name: character.name.clone(),
+                race: character.race.clone(),
+                main: character.main,
+                champion: character.champion,
+                merchant: character.merchant,
+                dead: !character.alive,
+                content_pack: character.content_pack,
+                aliases: model.aliases.aliases_for(AliasList::Character, key),
+            })
+            .collect();
+        let locations = model.locations
+            .iter()
+            .map(|(key, location)| {
+                // The internal name is added as an alias when the catalog is loaded.
+                let aliases = model.aliases.aliases_for(AliasList::Location, key)
+                    .into_iter()
+                    .filter(|alias| !location.internal_name.as_ref().is_some_and(|name| name.eq_ignore_ascii_case(alias)))
+                    .collect();
+                LocationEntry {
+                    name: location.name.clone(),
+                    typ: location.typ.clone(),
+                    parent: location.parent_location.map(|parent_id| model.location_name(parent_id).to_string()),
+                    content_pack: location.content_pack,
+                    internal_name: location.internal_name.clone(),
+                    map_point: location.map_point,
+                    dog_treasure: location.dog_treasure.clone(),
+                    challenge: location.challenge.clone(),
+                    aliases,
+                }
+            })
+            .collect();
+        let quests = model.quests
+            .iter()
+            .map(|(key, quest)| QuestEntry {
+                name: quest.name.clone(),
+                typ: quest.typ.clone(),
+                notes: quest.notes.clone(),
+                shrine: quest.shrine.map(|shrine_id| model.location_name(shrine_id).to_string()),
+                content_pack: quest.content_pack,
+                giver: quest.giver.map(|giver_id| model.character_name(giver_id).to_string()),
+                start_location: quest.start_location.map(|location_id| model.location_name(location_id).to_string()),
+                region: quest.region.map(|region_id| model.location_name(region_id).to_string()),
+                aliases: model.aliases.aliases_for(AliasList::Quest, key),
+            })
+            .collect();
+        let items = model.items_in_order()
+            .iter()
+            .map(|item| ItemEntry {
+                name: item.name.clone(),
+                typ: item.typ,
+                effect: item.effect,
+                effect_description: item.effect_description.clone(),
+                is_monster_part: item.is_monster_part,
+                mon_sell_price: item.mon_sell_price,
+                purchase_price: item.purchase_price.clone(),
+                upgrade_prices: item.upgrade_prices.clone(),
+                recipe: model.recipes.get(&item.name.to_lowercase()).map(|recipe| RecipeEntry {
+                    typ: recipe.typ,
+                    level: recipe.level,
+                    ingredients: recipe.ingredients.iter().map(|ingredient| ingredient.alternatives.clone()).collect(),
+                    method: recipe.method.clone(),
+                }),
+                content_pack: item.content_pack,
+                aliases: model.aliases.aliases_for(AliasList::Item, &item.name),
+            })
+            .collect();
+        let armor_sets = model.armor_sets
+            .values()
+            .map(|armor_set| ArmorSetEntry {
+                name: armor_set.name.clone(),
+                set_bonus: armor_set.set_bonus.clone(),
+                head: armor_set.head.map(|item_id| model.item_name(item_id).to_string()),
+                body: armor_set.body.map(|item_id| model.item_name(item_id).to_string()),
+                legs: armor_set.legs.map(|item_id| model.item_name(item_id).to_string()),
+                shop_locations: armor_set.shop_locations.iter().map(|location_id| model.location_name(*location_id).to_string()).collect(),
+                content_pack: armor_set.content_pack,
+            })
+            .collect();
+        Self {
+            version: CATALOG_VERSION,
+            characters,
+            locations,
+            quests,
+            items,
+            armor_sets,
+            map_entries: model.map_entries.clone(),
+        }
+    }
+
+    // The locations whose content pack is included and whose parents are all included too. As in
+    // the text files, a location under one that was left out is left out with it rather than
+    // becoming another root. A parent that isn't in the catalog is reported when the locations
+    // are linked.
+    fn included_location_entries(&self, content_options: &ContentOptions) -> Vec<&LocationEntry> {
+        let entries_by_name = self.locations
+            .iter()
+            .map(|entry| (entry.name.to_lowercase(), entry))
+            .collect::<BTreeMap<_, _>>();
+        self.locations
+            .iter()
+            .filter(|entry| {
+                let mut next = Some(*entry);
+                // The step limit keeps a loop in the parent names from running forever.
+                for _ in 0..=self.locations.len() {
+                    match next {
+                        Some(entry) if !content_options.includes(entry.content_pack) => return false,
+                        Some(entry) => next = entry.parent.as_ref().and_then(|parent_name| entries_by_name.get(&parent_name.to_lowercase()).copied()),
+                        None => break,
+                    }
+                }
+                true
+            })
+            .collect()
+    }
+
+    // Builds the same model that the text files would give, checking the references between
+    // entries along the way. Entries from content packs that aren't included are left out.
+    pub fn to_model(&self, content_options: &ContentOptions) -> Result<Model, Vec<ParseError>> {
+        let file_name = FILE_NAME_CATALOG;
+        if self.version > CATALOG_VERSION {
+            return Err(vec![ParseError::new(file_name, None, &format!("version {}", self.version), &format!("a catalog version no higher than {}", CATALOG_VERSION))]);
+        }
+        let mut model = Model::new_empty(content_options);
+        let mut errors = vec![];
+        let mut aliases = vec![];
+        for entry in self.characters.iter().filter(|entry| content_options.includes(entry.content_pack)) {
+            if model.characters.contains_key(&entry.name.to_lowercase()) {
+                errors.push(ParseError::new(file_name, None, &entry.name, "a character name that is not already defined"));
+                continue;
+            }
+            let mut character = Character::new(&entry.name, &entry.race, entry.main, entry.champion, entry.merchant, !entry.dead);
+            character.content_pack = entry.content_pack;
+            model.add_character(character);
+            aliases.extend(entry.aliases.iter().map(|alias| (AliasList::Character, alias.clone(), entry.name.clone())));
+        }
+        let location_entries = self.included_location_entries(content_options);
+        for entry in location_entries.iter() {
+            if model.locations.contains_key(&entry.name.to_lowercase()) {
+                errors.push(ParseError::new(file_name, None, &entry.name, "a location name that is not already defined"));
+                continue;
+            }
+            // The parent may come later in the list so it's filled in below.
+            let mut location = Location::new(&entry.name, entry.typ.clone(), None);
+            location.content_pack = entry.content_pack;
+            location.internal_name = entry.internal_name.clone();
+            location.map_point = entry.map_point;
+            location.dog_treasure = entry.dog_treasure.clone();
+            location.challenge = entry.challenge.clone();
+            model.add_location(location);
+            aliases.extend(entry.internal_name.iter().chain(entry.aliases.iter()).map(|alias| (AliasList::Location, alias.clone(), entry.name.clone())));
+        }
+        for entry in location_entries.iter() {
+            if let Some(parent_name) = &entry.parent {
+                match (model.locations.id(&entry.name.to_lowercase()), model.locations.id(&parent_name.to_lowercase())) {
+                    (Some(id), Some(parent_id)) => {
+                        if let Err(error) = model.set_parent_location(id, parent_id) {
+                            errors.push(ParseError::new(file_name, None, &entry.name, &error));
+                        }
+                    },
+                    // The location itself is only missing if it was a duplicate, which has
+                    // already been reported.
+                    (None, _) => {},
+                    (Some(_), None) => errors.push(ParseError::new(file_name, None, &entry.name, &format!("a known location as the parent \"{}\"", parent_name))),
+                }
+            }
+        }
+        for entry in self.quests.iter().filter(|entry| content_options.includes(entry.content_pack)) {
+            if model.quests.contains_key(&entry.name.to_lowercase()) {
+                errors.push(ParseError::new(file_name, None, &entry.name, "a quest name that is not already defined"));
+                continue;
+            }
+            let mut quest = match (&entry.typ, &entry.shrine) {
+                (QuestType::Main, _) => Quest::new_main(&entry.name),
+                (QuestType::Side, _) => Quest::new_side(&entry.name, entry.notes.clone()),
+                (QuestType::Shrine, Some(shrine_name)) => {
+                    let shrine_id = model.locations.id(&shrine_name.to_lowercase());
+                    match shrine_id.map(|shrine_id| (shrine_id, model.locations.by_id(shrine_id))) {
+                        Some((shrine_id, location)) if location.typ == LocationType::Shrine && location.quest.is_none() => {
+                            Quest::new_shrine(&entry.name, shrine_id)
+                        },
+                        _ => {
+                            errors.push(ParseError::new(file_name, None, &entry.name, &format!("a known shrine without a quest as \"{}\"", shrine_name)));
+                            continue;
+                        }
+                    }
+                },
+                (QuestType::Shrine, None) => {
+                    errors.push(ParseError::new(file_name, None, &entry.name, "a shrine for the shrine quest"));
+                    continue;
+                },
+            };
+            quest.content_pack = entry.content_pack;
+            if let Err(error) = model.link_quest(&mut quest, entry.giver.as_deref(), entry.start_location.as_deref(), entry.region.as_deref()) {
+                errors.push(ParseError::new(file_name, None, &entry.name, &error));
+            }
+            let shrine_id = quest.shrine;
+            let quest_id = model.add_quest(quest);
+            if let Some(shrine_id) = shrine_id {
+                model.locations.by_id_mut(shrine_id).quest = Some(quest_id);
+            }
+            aliases.extend(entry.aliases.iter().map(|alias| (AliasList::Quest, alias.clone(), entry.name.clone())));
+        }
+        let item_entries = self.items.iter().filter(|entry| content_options.includes(entry.content_pack)).collect::<Vec<_>>();
+        for entry in item_entries.iter() {
+            if model.items.contains_key(&entry.name.to_lowercase()) {
+                errors.push(ParseError::new(file_name, None, &entry.name, "an item name that is not already defined"));
+                continue;
+            }
+            let sequence = model.items.len();
+            let mut item = Item::new(&entry.name, entry.typ, sequence);
+            item.effect = entry.effect;
+            item.effect_description = entry.effect_description.clone();
+            item.is_monster_part = entry.is_monster_part;
+            item.mon_sell_price = entry.mon_sell_price;
+            item.purchase_price = entry.purchase_price.clone();
+            item.upgrade_prices = entry.upgrade_prices.clone();
+            item.content_pack = entry.content_pack;
+            model.add_item(item);
+            aliases.extend(entry.aliases.iter().map(|alias| (AliasList::Item, alias.clone(), entry.name.clone())));
+        }
+        // Prices and recipes can name any item so they're checked once all of the items are in.
+        for entry in item_entries.iter() {
+            for price in entry.purchase_price.iter().chain(entry.upgrade_prices.iter()) {
+                for component in price.components.iter() {
+                    if let PriceComponent::Item { name, .. } = component {
+                        if !model.items.contains_key(&name.to_lowercase()) {
+                            errors.push(ParseError::new(file_name, None, &entry.name, &format!("a known item in the price instead of \"{}\"", name)));
+                        }
+                    }
+                }
+            }
+            if let Some(recipe_entry) = &entry.recipe {
+                let mut recipe = Recipe::new(&entry.name, recipe_entry.typ);
+                recipe.level = recipe_entry.level;
+                recipe.method = recipe_entry.method.clone();
+                recipe.content_pack = entry.content_pack;
+                for alternatives in recipe_entry.ingredients.iter() {
+                    for alternative in alternatives.iter().filter(|alternative| !model.items.contains_key(&alternative.to_lowercase())) {
+                        errors.push(ParseError::new(file_name, None, &entry.name, &format!("a known item as the ingredient \"{}\"", alternative)));
+                    }
+                    recipe.ingredients.push(Ingredient::new(alternatives.clone()));
+                }
+                if !model.recipes.contains_key(&entry.name.to_lowercase()) {
+                    model.add_recipe(recipe);
+                }
+            }
+        }
+        for entry in self.armor_sets.iter().filter(|entry| content_options.includes(entry.content_pack)) {
+            if model.armor_sets.contains_key(&entry.name.to_lowercase()) {
+                errors.push(ParseError::new(file_name, None, &entry.name, "an armor set name that is not already defined"));
+                continue;
+            }
+            let mut armor_set = ArmorSet::new(&entry.name, entry.set_bonus.clone());
+            armor_set.content_pack = entry.content_pack;
+            for (slot, piece) in [(ArmorSlot::Head, &entry.head), (ArmorSlot::Body, &entry.body), (ArmorSlot::Legs, &entry.legs)].iter() {
+                // A piece from a content pack that isn't included is left out of the set.
+                if let Some(item_name) = piece {
+                    match model.items.id(&item_name.to_lowercase()) {
+                        Some(item_id) if model.items.by_id(item_id).typ == ItemType::Armor => armor_set.set_piece(*slot, item_id),
+                        Some(_) => errors.push(ParseError::new(file_name, None, &entry.name, &format!("a piece of armor instead of \"{}\"", item_name))),
+                        None if self.items.iter().any(|item_entry| item_entry.name.to_lowercase() == item_name.to_lowercase()) => {},
+                        None => errors.push(ParseError::new(file_name, None, &entry.name, &format!("a known item as the piece \"{}\"", item_name))),
+                    }
+                }
+            }
+            for shop_location in entry.shop_locations.iter() {
+                match model.locations.id(&shop_location.to_lowercase()) {
+                    Some(location_id) => armor_set.shop_locations.push(location_id),
+                    None => errors.push(ParseError::new(file_name, None, &entry.name, &format!("a known location as the shop \"{}\"", shop_location))),
+                }
+            }
+            model.add_armor_set(armor_set);
+        }
+        for (alias_list, alias, name) in aliases.iter() {
+            model.aliases.add(*alias_list, alias, name);
+        }
+        model.map_entries = self.map_entries.clone();
+        if let Err(mut link_errors) = model.link_acquisition_sources(file_name) {
+            errors.append(&mut link_errors);
+        }
+        if errors.is_empty() {
+            Ok(model)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn catalog_json(model: &Model) -> String {
+        serde_json::to_string_pretty(&Catalog::from_model(model)).unwrap()
+    }
+
+    #[test]
+    fn structured_catalog_gives_the_same_model_as_the_text_files() {
+        let path = data_source::copy_embedded_files_to_temp("catalog_same_model");
+        let source = DataSource::new_directory(&path);
+        write_catalog(&Model::load_text(&source, &ContentOptions::new_all()).unwrap(), &path).unwrap();
+        for content_options in [ContentOptions::new_all(), ContentOptions::new_base_game(), ContentOptions::default()].iter() {
+            let text_model = Model::load_text(&source, content_options).unwrap();
+            let structured_model = load_model(&source, content_options).unwrap();
+            assert_eq!(catalog_json(&text_model), catalog_json(&structured_model));
+        }
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn stale_structured_catalog_is_not_chosen() {
+        let path = data_source::copy_embedded_files_to_temp("catalog_stale");
+        let source = DataSource::new_directory(&path);
+        write_catalog(&Model::load_text(&source, &ContentOptions::new_all()).unwrap(), &path).unwrap();
+        assert_eq!(choose_format(&source).unwrap(), CatalogFormat::Structured);
+
+        let later = SystemTime::now() + Duration::from_secs(60);
+        fs::OpenOptions::new().write(true).open(path.join(parse::FILE_NAME_QUESTS)).unwrap().set_modified(later).unwrap();
+        assert!(choose_format(&source).is_err());
+        assert!(Model::load_format(&source, &ContentOptions::default(), None).is_err());
+        // Either format can still be loaded by choosing it.
+        assert!(Model::load_format(&source, &ContentOptions::default(), Some(CatalogFormat::Text)).is_ok());
+        assert!(Model::load_format(&source, &ContentOptions::default(), Some(CatalogFormat::Structured)).is_ok());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn parent_loops_are_errors() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::new_all()).unwrap();
+        let mut catalog = Catalog::from_model(&model);
+        let area_name = model.location_name(model.get_location("Hebra").child_locations[0]).to_string();
+        for entry in catalog.locations.iter_mut() {
+            if entry.name == "Hebra" {
+                entry.parent = Some(area_name.clone());
+            } else if entry.name == "Akkala" {
+                entry.parent = Some("Akkala".to_string());
+            }
+        }
+        let errors = catalog.to_model(&ContentOptions::new_all()).unwrap_err();
+        let names = errors.iter().map(|error| error.line.as_str()).collect::<Vec<_>>();
+        assert!(names.contains(&"Akkala"));
+        assert!(names.contains(&"Hebra") || names.contains(&area_name.as_str()));
+    }
+}
@@ -1,9 +1,14 @@
 use super::game_record::*;
+use super::journal::{Journal, JournalError};
 use super::model::*;
+use super::parse;
 
 use util::format;
 
 const MAX_SUGGESTIONS: usize = 20;
+// Characters don't die in the normal course of the game, so a death is only offered when it's
+// asked for, as in "died kass".
+const PREFIX_CHARACTER_DEATH: &str = "died ";
 
 #[derive(Debug)]
 pub struct CommandSet {
@@ -57,13 +62,14 @@ pub enum TargetType {
     Shield,
     Special,
     Stamina,
+    Weapon,
 }
 
 impl CommandSet {
     pub fn new(partial_name: &str, number: Option<usize>) -> Self {
         Self {
             partial_name: partial_name.to_lowercase(),
-            number: number,
+            number,
             include_empty_targets: true,
             force_number_events: false,
             add_to_special_commands: false,
@@ -100,6 +106,7 @@ impl CommandSet {
             "he" => self.gen_no_target(model, GameEventType::SetHearts),
             "st" => self.gen_no_target(model, GameEventType::SetStamina),
             "die" => self.gen_no_target(model, GameEventType::LinkDeath),
+            name if name.starts_with(PREFIX_CHARACTER_DEATH) => self.gen_character_deaths(model),
             _ => {},
         };
         if self.add_to_special_commands || self.targets.is_empty() {
@@ -108,21 +115,18 @@ impl CommandSet {
                 self.gen_locations(model);
                 self.gen_quests(model);
             }
+            self.gen_items(model);
         }
         let command_count = self.command_count();
         if command_count <= MAX_SUGGESTIONS || self.force_number_events {
             self.number_targets = false;
-            let mut command_number = 1;
-            for command_event in self.targets.iter_mut().map(|target| target.events.iter_mut()).flatten() {
+            for (command_number, command_event) in (1..).zip(self.targets.iter_mut().flat_map(|target| target.events.iter_mut())) {
                 command_event.command_number = Some(command_number);
-                command_number += 1;
             }
         } else {
             self.number_targets = true;
-            let mut command_number = 1;
-            for command_target in self.targets.iter_mut() {
+            for (command_number, command_target) in (1..).zip(self.targets.iter_mut()) {
                 command_target.command_number = Some(command_number);
-                command_number += 1;
             }
         }
     }
@@ -148,18 +152,13 @@ impl CommandSet {
     }
 
     fn gen_characters(&mut self, model: &Model) {
-        let partial_name = self.partial_name.clone();
-        for (_key, character) in model
-            .characters
-            .iter()
-            .filter(|(key, _character)| key.contains(&partial_name)) {
-
+        for character in model.query_characters().matching(&self.partial_name).run() {
             let status = character.status_description(model);
             let mut target = CommandTarget::new(ModelList::Character, TargetType::Character, &character.name, &status);
             if !character.is_mentioned() {
                 target.events.push(CommandEvent::new(GameEventType::MentionCharacter, None));
             }
-            if !character.is_met() && character.alive {
+            if !character.is_met() && character.is_alive() {
                 target.events.push(CommandEvent::new(GameEventType::MeetCharacter, None));
             }
             if !character.is_met_in_flashback() {
@@ -171,16 +170,18 @@ impl CommandSet {
         }
     }
 
-    fn gen_locations(&mut self, model: &Model) {
-        let partial_name = self.partial_name.clone();
-        for (_key, location) in model
-            .locations
-            .iter()
-            .filter(|(key, location)|
-                key.contains(&partial_name)
-                    || location.challenge.as_ref().map_or(false, |challenge| challenge.to_lowercase().contains(&partial_name))
-            ) {
+    fn gen_character_deaths(&mut self, model: &Model) {
+        let partial_name = self.partial_name[PREFIX_CHARACTER_DEATH.len()..].trim();
+        for character in model.query_characters().matching(partial_name).alive(true).run() {
+            let status = character.status_description(model);
+            let mut target = CommandTarget::new(ModelList::Character, TargetType::Character, &character.name, &status);
+            target.events.push(CommandEvent::new(GameEventType::CharacterDeath, None));
+            self.targets.push(target);
+        }
+    }
 
+    fn gen_locations(&mut self, model: &Model) {
+        for location in model.query_locations().matching(&self.partial_name).run() {
             let status = location.status_description(model);
             let mut target = CommandTarget::new(ModelList::Location, TargetType::Location, &location.name, &status);
             if !location.is_discovered() {
@@ -198,11 +199,12 @@ impl CommandSet {
                         target.events.push(CommandEvent::new(GameEventType::CompleteShrine, None));
                     }
                 },
-                LocationType::TechLab => {
-                    if !location.is_flame_lit() {
-                        target.events.push(CommandEvent::new(GameEventType::LightFlame, None));
-                    }
-                }
+                LocationType::TechLab if !location.is_flame_lit() => {
+                    target.events.push(CommandEvent::new(GameEventType::LightFlame, None));
+                },
+                LocationType::Tower if !location.is_activated() => {
+                    target.events.push(CommandEvent::new(GameEventType::ActivateTower, None));
+                },
                 _ => {}
             }
             if self.include_empty_targets || !target.events.is_empty() {
@@ -212,12 +214,7 @@ impl CommandSet {
     }
     
     fn gen_quests(&mut self, model: &Model) {
-        let partial_name = self.partial_name.clone();
-        for (_key, quest) in model
-            .quests
-            .iter()
-            .filter(|(key, _quest)| key.contains(&partial_name)) {
-
+        for quest in model.query_quests().matching(&self.partial_name).run() {
             let status = quest.status_description(model);
             let mut target = CommandTarget::new(ModelList::Quest, TargetType::Quest, &quest.name, &status);
             if !quest.is_started() {
@@ -232,6 +229,34 @@ impl CommandSet {
         }
     }
 
+    // With a number, the only command for an item is to set how many there are.
+    fn gen_items(&mut self, model: &Model) {
+        for item in model.query_items().matching(&self.partial_name).run() {
+            let mut target = CommandTarget::new(ModelList::Item, TargetType::from_item(item), &item.name, &item.status_description());
+            match self.number {
+                Some(number) => {
+                    if number != item.count {
+                        target.events.push(CommandEvent::new(GameEventType::SetItemCount, Some(number)));
+                    }
+                },
+                None => {
+                    if !item.is_identified() {
+                        target.events.push(CommandEvent::new(GameEventType::IdentifyItem, None));
+                    }
+                    if !item.is_in_compendium() {
+                        target.events.push(CommandEvent::new(GameEventType::AddToCompendium, None));
+                    }
+                    if item.can_upgrade() {
+                        target.events.push(CommandEvent::new(GameEventType::SetArmorLevel, Some(item.armor_level + 1)));
+                    }
+                },
+            }
+            if self.include_empty_targets || !target.events.is_empty() {
+                self.targets.push(target);
+            }
+        }
+    }
+
     fn get_current_count_no_target(model: &Model, event_type: &GameEventType) -> usize {
         match event_type {
             GameEventType::BloodMoon => model.blood_moons,
@@ -248,7 +273,7 @@ impl CommandSet {
     }
 
     pub fn command_count(&self) -> usize {
-        self.targets.iter().map(|target| target.events.iter()).flatten().count()
+        self.targets.iter().flat_map(|target| target.events.iter()).count()
     }
 
     pub fn print_numbered(&self, model: &Model) {
@@ -284,16 +309,18 @@ impl CommandSet {
         command_set
     }
 
-    pub fn apply_command(&self, model: &mut Model, game_record: &mut GameRecord, time: usize, command_number: usize) {
+    // The events are added through the journal so that they're on disk as soon as they're applied.
+    pub fn apply_command(&self, model: &mut Model, game_record: &mut GameRecord, journal: &mut Journal, time: usize, command_number: usize) -> Result<(), JournalError> {
         assert!(!self.number_targets);
         for target in self.targets.iter() {
             for event in target.events.iter() {
                 if event.command_number.unwrap() == command_number {
                     let game_event = GameEvent::new(time, event.typ.clone(), &target.name, event.number);
-                    game_record.add_event(model, game_event);
+                    journal.add_event(game_record, model, game_event)?;
                 }
             }
         }
+        Ok(())
     }
 
 }
@@ -337,12 +364,31 @@ impl TargetType {
             TargetType::Shield => "Shield",
             TargetType::Special => "Special",
             TargetType::Stamina => "Stamina",
+            TargetType::Weapon => "Weapon",
+        }
+    }
+
+    pub fn from_item(item: &Item) -> Self {
+        match item.typ {
+            ItemType::Armor => TargetType::Armor,
+            ItemType::Arrow | ItemType::Bow => TargetType::Bow,
+            ItemType::Material if item.is_monster_part => TargetType::Monster,
+            ItemType::Material => TargetType::Material,
+            ItemType::Shield => TargetType::Shield,
+            ItemType::Weapon => TargetType::Weapon,
+            ItemType::Food | ItemType::KeyItem | ItemType::Money => TargetType::Special,
         }
     }
 }
 
 pub fn try_suggest_commands() {
-    let model = Model::new();
+    let model = match Model::new() {
+        Ok(model) => model,
+        Err(errors) => {
+            parse::print_errors(&errors);
+            return;
+        }
+    };
     //bg!(&model.get_location("Mezza Lo Shrine"));
 
     // CommandSet::generate(&model, "MEZ", None).print_numbered();
@@ -350,4 +396,25 @@ pub fn try_suggest_commands() {
     // CommandSet::generate(&model, "bridge", None).print_numbered();
     // CommandSet::generate(&model, "blessing", None).print_numbered(&model);
     CommandSet::new_gen(&model, "test", None).print_numbered(&model);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::data_source::DataSource;
+
+    fn event_types(command_set: &CommandSet) -> Vec<&str> {
+        command_set.targets.iter().flat_map(|target| target.events.iter()).map(|event| event.typ.variant_to_string()).collect()
+    }
+
+    #[test]
+    fn character_death_is_only_offered_when_asked_for() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        assert!(!event_types(&CommandSet::new_gen(&model, "kass", None)).contains(&"CharacterDeath"));
+        let command_set = CommandSet::new_gen(&model, "died kass", None);
+        assert_eq!(vec!["CharacterDeath"], event_types(&command_set));
+        assert_eq!("Kass", command_set.targets[0].name);
+        // Someone who's already dead can't die again.
+        assert!(CommandSet::new_gen(&model, "died mipha", None).targets.is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+use serde::Serialize;
+
+use super::arena::LocationId;
+use super::model::*;
+
+// The completion percentage on the map screen. The game counts the 120 shrines, the 900 Korok
+// seeds, and 226 named map locations, and each one is worth the same, so a shrine and a seed each
+// add about 0.08%. DLC content doesn't count toward it.
+//
+// A shrine counts as soon as it's found; it doesn't have to be completed. The map locations are
+// the ones that were joined to the datamined map file when the model was loaded. The file has
+// fewer entries than the game counts, so the map locations as a whole are given the game's share
+// of the percentage and each entry is worth a little more than a shrine.
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum CompletionCategory {
+    Shrines,
+    KorokSeeds,
+    MapLocations,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub struct Tally {
+    pub found: usize,
+    pub total: usize,
+}
+
+#[derive(Debug)]
+pub struct RegionCompletion {
+    pub region: LocationId,
+    pub shrines: Tally,
+    pub map_locations: Tally,
+}
+
+#[derive(Debug)]
+pub struct Completion {
+    pub shrines: Tally,
+    pub korok_seeds: Tally,
+    pub map_locations: Tally,
+    // In region name order. Korok seeds are only tracked as a count so they aren't broken down by
+    // region.
+    pub regions: Vec<RegionCompletion>,
+    // What's left for 100%, each list in name order.
+    pub missing_shrines: Vec<LocationId>,
+    pub missing_map_locations: Vec<LocationId>,
+    pub missing_korok_seeds: usize,
+}
+
+impl CompletionCategory {
+    pub fn variant_to_string<'a>(&self) -> &'a str {
+        match self {
+            CompletionCategory::Shrines => "Shrines",
+            CompletionCategory::KorokSeeds => "Korok Seeds",
+            CompletionCategory::MapLocations => "Map Locations",
+        }
+    }
+
+    // How many of the category the game counts, which is its share of the percentage.
+    pub fn game_count(&self) -> usize {
+        match self {
+            CompletionCategory::Shrines => SHRINE_COUNT,
+            CompletionCategory::KorokSeeds => KOROK_SEED_COUNT,
+            CompletionCategory::MapLocations => MAP_LOCATION_COUNT,
+        }
+    }
+}
+
+impl Tally {
+    pub fn new(found: usize, total: usize) -> Self {
+        Self {
+            found,
+            total,
+        }
+    }
+
+    // A count entered by hand, such as Korok seeds, can be more than the total.
+    pub fn missing(&self) -> usize {
+        self.total.saturating_sub(self.found)
+    }
+
+    // A tally with nothing to find, like the shrines in a region that has none, is complete.
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.found as f64 * 100.0 / self.total as f64
+        }
+    }
+
+    pub fn add(&self, other: &Tally) -> Tally {
+        Tally::new(self.found + other.found, self.total + other.total)
+    }
+}
+
+impl RegionCompletion {
+    pub fn tally(&self) -> Tally {
+        self.shrines.add(&self.map_locations)
+    }
+}
+
+impl Completion {
+    pub fn categories(&self) -> Vec<(CompletionCategory, &Tally)> {
+        vec![
+            (CompletionCategory::Shrines, &self.shrines),
+            (CompletionCategory::KorokSeeds, &self.korok_seeds),
+            (CompletionCategory::MapLocations, &self.map_locations),
+        ]
+    }
+
+    pub fn tally(&self) -> Tally {
+        self.shrines.add(&self.korok_seeds).add(&self.map_locations)
+    }
+
+    // Each category is weighted by the game's count rather than by how many are in the catalog.
+    pub fn percent(&self) -> f64 {
+        let categories = self.categories();
+        let weight = categories.iter().map(|(category, _)| category.game_count()).sum::<usize>() as f64;
+        // Dividing once at the end keeps a complete game at exactly 100%.
+        categories
+            .iter()
+            .map(|(category, tally)| tally.percent() * category.game_count() as f64)
+            .sum::<f64>() / weight
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.tally().missing() == 0
+    }
+}
+
+// The percentage the way the game shows it, cut off rather than rounded so that it doesn't show
+// 100.00% until everything has been found.
+pub fn format_percent(percent: f64) -> String {
+    format!("{:.2}%", (percent * 100.0).floor() / 100.0)
+}
+
+impl Model {
+    pub fn completion(&self) -> Completion {
+        let counted = |location: &Location| location.content_pack == ContentPack::Base;
+        let shrines = self.locations
+            .iter_ids()
+            .filter(|(_, location)| counted(location) && location.typ == LocationType::Shrine)
+            .collect::<Vec<_>>();
+        let map_locations = self.locations
+            .iter_ids()
+            .filter(|(_, location)| counted(location) && location.internal_name.is_some())
+            .collect::<Vec<_>>();
+
+        let mut regions = self.root_locations()
+            .into_iter()
+            .filter(|id| self.locations.by_id(*id).typ == LocationType::Region)
+            .map(|region| RegionCompletion {
+                region,
+                shrines: Tally::new(0, 0),
+                map_locations: Tally::new(0, 0),
+            })
+            .collect::<Vec<_>>();
+        for (is_shrine, locations) in [(true, &shrines), (false, &map_locations)].iter() {
+            for (id, location) in locations.iter() {
+                let region_id = self.get_containing_region_id(*id);
+                if let Some(region) = regions.iter_mut().find(|region| Some(region.region) == region_id) {
+                    let tally = if *is_shrine { &mut region.shrines } else { &mut region.map_locations };
+                    tally.total += 1;
+                    if location.is_discovered() {
+                        tally.found += 1;
+                    }
+                }
+            }
+        }
+
+        let missing = |locations: &[(LocationId, &Location)]| locations
+            .iter()
+            .filter(|(_, location)| !location.is_discovered())
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        let missing_shrines = missing(&shrines);
+        let missing_map_locations = missing(&map_locations);
+
+        let korok_seeds = Tally::new(self.korok_seeds.min(KOROK_SEED_COUNT), KOROK_SEED_COUNT);
+        Completion {
+            shrines: Tally::new(shrines.len() - missing_shrines.len(), SHRINE_COUNT),
+            korok_seeds,
+            map_locations: Tally::new(map_locations.len() - missing_map_locations.len(), map_locations.len()),
+            regions,
+            missing_korok_seeds: korok_seeds.missing(),
+            missing_shrines,
+            missing_map_locations,
+        }
+    }
+
+    pub fn report_completion(&self) {
+        let completion = self.completion();
+        println!("\nCompletion: {}", format_percent(completion.percent()));
+        for (category, tally) in completion.categories() {
+            println!("\t{}: {} of {} ({})", category.variant_to_string(), tally.found, tally.total, format_percent(tally.percent()));
+        }
+        println!("\nCompletion by Region:");
+        for region in completion.regions.iter() {
+            println!("\t{}: {} ({} of {} shrines, {} of {} map locations)", self.location_name(region.region), format_percent(region.tally().percent()),
+                region.shrines.found, region.shrines.total, region.map_locations.found, region.map_locations.total);
+        }
+        if completion.is_complete() {
+            return;
+        }
+        println!("\nMissing:");
+        for id in completion.missing_shrines.iter() {
+            println!("\tShrine: {}", self.location_name(*id));
+        }
+        for id in completion.missing_map_locations.iter() {
+            println!("\tMap location: {}", self.location_name(*id));
+        }
+        if completion.missing_korok_seeds > 0 {
+            println!("\tKorok seeds: {}", completion.missing_korok_seeds);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::data_source::DataSource;
+
+    fn load() -> Model {
+        Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap()
+    }
+
+    fn discover(model: &mut Model, is_discovered: impl Fn(&Location) -> bool) {
+        for location in model.locations.values_mut() {
+            if is_discovered(location) {
+                location.discovered_time = 1;
+            }
+        }
+    }
+
+    fn assert_percent(completion: &Completion, count: usize) {
+        let expected = count as f64 * 100.0 / (SHRINE_COUNT + KOROK_SEED_COUNT + MAP_LOCATION_COUNT) as f64;
+        assert!((completion.percent() - expected).abs() < 1e-9, "{} != {}", completion.percent(), expected);
+    }
+
+    #[test]
+    fn every_map_entry_counts_toward_completion() {
+        let completion = load().completion();
+        assert_eq!(completion.shrines.total, SHRINE_COUNT);
+        assert_eq!(completion.map_locations.total, load().map_entries.len());
+        assert_eq!(completion.missing_map_locations.len(), completion.map_locations.total);
+        assert_eq!(completion.percent(), 0.0);
+    }
+
+    #[test]
+    fn categories_are_weighted_by_the_game_count() {
+        let mut model = load();
+        discover(&mut model, |location| location.typ == LocationType::Shrine);
+        assert_percent(&model.completion(), SHRINE_COUNT);
+
+        let mut model = load();
+        model.korok_seeds = KOROK_SEED_COUNT;
+        assert_percent(&model.completion(), KOROK_SEED_COUNT);
+
+        let mut model = load();
+        discover(&mut model, |location| location.internal_name.is_some());
+        assert_percent(&model.completion(), MAP_LOCATION_COUNT);
+
+        let mut model = load();
+        model.get_location_mut("Oman Au Shrine").discovered_time = 1;
+        assert_percent(&model.completion(), 1);
+    }
+
+    #[test]
+    fn fully_discovered_model_is_complete() {
+        let mut model = load();
+        discover(&mut model, |_| true);
+        model.korok_seeds = KOROK_SEED_COUNT;
+        let completion = model.completion();
+        assert!(completion.is_complete());
+        assert_eq!(completion.percent(), 100.0);
+        assert_eq!(format_percent(completion.percent()), "100.00%");
+        assert!(completion.missing_shrines.is_empty() && completion.missing_map_locations.is_empty());
+        assert!(completion.regions.iter().all(|region| region.tally().missing() == 0));
+    }
+}
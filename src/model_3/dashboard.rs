@@ -0,0 +1,196 @@
+use serde::Serialize;
+
+use super::arena::LocationId;
+use super::completion::{format_percent, Tally};
+use super::lookup::LookupError;
+use super::model::*;
+
+// Progress in one region, taken from the location tree and whatever has happened in the game so
+// far. It only holds counts and names so it can be printed, turned into HTML, or written out as
+// JSON as is.
+//
+// Characters aren't tied to a place in the catalog, so the characters met in a region are counted
+// among the ones who give its side quests. Only the side quests with a "giver:" note in the Quests
+// file have a giver, so many regions have no characters to count and the line is left out.
+#[derive(Debug, Serialize)]
+pub struct RegionDashboard {
+    pub region: String,
+    pub towers_activated: Tally,
+    pub shrines_discovered: Tally,
+    pub shrines_started: Tally,
+    pub shrines_completed: Tally,
+    pub dog_treasures_found: Tally,
+    // Everything under the region except the shrines, which are counted above.
+    pub locations_discovered: Tally,
+    pub side_quests: QuestProgress,
+    pub characters_met: Tally,
+    // What's left to do, each list in name order.
+    pub next: NextSteps,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct QuestProgress {
+    pub not_started: usize,
+    pub open: usize,
+    pub done: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct NextSteps {
+    // Towers that haven't been activated, whether or not they've been found.
+    pub towers: Vec<String>,
+    // Shrines that have been found but not completed.
+    pub shrines: Vec<String>,
+    pub dog_treasures: Vec<String>,
+    pub side_quests: Vec<String>,
+}
+
+impl QuestProgress {
+    pub fn total(&self) -> usize {
+        self.not_started + self.open + self.done
+    }
+}
+
+impl Model {
+    // One dashboard for each region, in name order.
+    pub fn region_dashboards(&self) -> Vec<RegionDashboard> {
+        self.root_locations()
+            .into_iter()
+            .filter(|id| self.locations.by_id(*id).typ == LocationType::Region)
+            .map(|id| self.region_dashboard_by_id(id))
+            .collect()
+    }
+
+    pub fn region_dashboard(&self, region_name: &str) -> Result<RegionDashboard, LookupError> {
+        let id = self.try_location_id(region_name)?;
+        check_location_type(self.locations.by_id(id), LocationType::Region)?;
+        Ok(self.region_dashboard_by_id(id))
+    }
+
+    fn region_dashboard_by_id(&self, region_id: LocationId) -> RegionDashboard {
+        let locations = self.descendants(region_id).map(|id| self.locations.by_id(id)).collect::<Vec<_>>();
+        let of_type = |typ: LocationType| locations.iter().copied().filter(|location| location.typ == typ).collect::<Vec<_>>();
+        let towers = of_type(LocationType::Tower);
+        let shrines = of_type(LocationType::Shrine);
+        let dog_treasures = locations.iter().copied().filter(|location| location.has_dog_treasure()).collect::<Vec<_>>();
+        let others = locations.iter().copied().filter(|location| location.typ != LocationType::Shrine).collect::<Vec<_>>();
+
+        let side_quests = self.quests
+            .values()
+            .filter(|quest| quest.typ == QuestType::Side)
+            .filter(|quest| quest.start_location.or(quest.region).is_some_and(|location_id| self.location_is_within(location_id, region_id)))
+            .collect::<Vec<_>>();
+        let mut quest_progress = QuestProgress::default();
+        for quest in side_quests.iter() {
+            if quest.is_completed() {
+                quest_progress.done += 1;
+            } else if quest.is_started() {
+                quest_progress.open += 1;
+            } else {
+                quest_progress.not_started += 1;
+            }
+        }
+        let mut giver_ids = side_quests.iter().filter_map(|quest| quest.giver).collect::<Vec<_>>();
+        giver_ids.sort();
+        giver_ids.dedup();
+        let characters_met = giver_ids.iter().filter(|id| self.characters.by_id(**id).is_met()).count();
+
+        let names = |locations: &[&Location], left: fn(&Location) -> bool| {
+            let mut names = locations.iter().filter(|location| left(location)).map(|location| location.name.clone()).collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        let mut open_side_quests = side_quests.iter().filter(|quest| quest.is_started() && !quest.is_completed()).map(|quest| quest.name.clone()).collect::<Vec<_>>();
+        open_side_quests.sort();
+
+        RegionDashboard {
+            region: self.location_name(region_id).to_string(),
+            towers_activated: tally(&towers, Location::is_activated),
+            shrines_discovered: tally(&shrines, Location::is_discovered),
+            shrines_started: tally(&shrines, Location::is_started),
+            shrines_completed: tally(&shrines, Location::is_completed),
+            dog_treasures_found: tally(&dog_treasures, Location::is_dog_treasure_found),
+            locations_discovered: tally(&others, Location::is_discovered),
+            side_quests: quest_progress,
+            characters_met: Tally::new(characters_met, giver_ids.len()),
+            next: NextSteps {
+                towers: names(&towers, |location| !location.is_activated()),
+                shrines: names(&shrines, |location| location.is_discovered() && !location.is_completed()),
+                dog_treasures: names(&dog_treasures, |location| location.is_discovered() && !location.is_dog_treasure_found()),
+                side_quests: open_side_quests,
+            },
+        }
+    }
+
+    pub fn report_region_dashboards(&self) {
+        for dashboard in self.region_dashboards() {
+            println!("\n{}", format_region_dashboard(&dashboard));
+        }
+    }
+}
+
+fn tally(locations: &[&Location], found: fn(&Location) -> bool) -> Tally {
+    Tally::new(locations.iter().filter(|location| found(location)).count(), locations.len())
+}
+
+pub fn format_region_dashboard(dashboard: &RegionDashboard) -> String {
+    let line = |label: &str, tally: &Tally| format!("\t{}: {} of {} ({})", label, tally.found, tally.total, format_percent(tally.percent()));
+    let mut lines = vec![
+        dashboard.region.clone(),
+        line("Towers activated", &dashboard.towers_activated),
+        line("Shrines discovered", &dashboard.shrines_discovered),
+        line("Shrines started", &dashboard.shrines_started),
+        line("Shrines completed", &dashboard.shrines_completed),
+        line("Dog treasures found", &dashboard.dog_treasures_found),
+        line("Locations discovered", &dashboard.locations_discovered),
+        format!("\tSide quests: {} done, {} open, {} not started", dashboard.side_quests.done, dashboard.side_quests.open, dashboard.side_quests.not_started),
+    ];
+    if dashboard.characters_met.total > 0 {
+        lines.push(line("Characters met", &dashboard.characters_met));
+    }
+    for (label, names) in [("Towers to activate", &dashboard.next.towers), ("Shrines to finish", &dashboard.next.shrines),
+            ("Dog treasures to find", &dashboard.next.dog_treasures), ("Open side quests", &dashboard.next.side_quests)].iter() {
+        if !names.is_empty() {
+            lines.push(format!("\t{}: {}", label, names.join(", ")));
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::data_source::DataSource;
+    use super::super::game_record::{GameEvent, GameEventType, GameRecord};
+
+    #[test]
+    fn dashboard_follows_the_applied_events() {
+        let mut model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        let mut game_record = GameRecord::new("Dashboard");
+        for (time, typ, name) in [
+                (10, GameEventType::DiscoverLocation, "Dueling Peaks Tower"),
+                (20, GameEventType::CompleteShrine, "Ta'loh Naeg Shrine"),
+                (30, GameEventType::StartQuest, "Flown the Coop"),
+                (40, GameEventType::MeetCharacter, "Cado")].iter() {
+            game_record.add_event(&mut model, GameEvent::new(*time, typ.clone(), name, None)).unwrap();
+        }
+
+        let dashboard = model.region_dashboard("Dueling Peaks").unwrap();
+        assert_eq!(dashboard.towers_activated, Tally::new(0, 1));
+        assert_eq!(dashboard.next.towers, vec!["Dueling Peaks Tower"]);
+        assert_eq!(dashboard.shrines_discovered.found, 1);
+        assert_eq!(dashboard.shrines_completed.found, 1);
+        assert!(dashboard.next.shrines.is_empty());
+        assert_eq!(dashboard.side_quests.open, 1);
+        assert_eq!(dashboard.next.side_quests, vec!["Flown the Coop"]);
+        assert_eq!(dashboard.characters_met.found, 1);
+        // The tower, the village the quest starts in, and the area they and the shrine are in.
+        assert_eq!(dashboard.locations_discovered.found, 3);
+
+        game_record.add_event(&mut model, GameEvent::new(50, GameEventType::ActivateTower, "Dueling Peaks Tower", None)).unwrap();
+        let dashboard = model.region_dashboard("Dueling Peaks").unwrap();
+        assert_eq!(dashboard.towers_activated, Tally::new(1, 1));
+        assert!(dashboard.next.towers.is_empty());
+        assert!(model.region_dashboard("Akkala Tower").is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::parse::*;
+
+// If this environment variable is set it names the directory holding the catalog files.
+pub const ENV_DATA_PATH: &str = "BOTW_DATA_PATH";
+
+// Copies of the shipped catalog files, built into the binary so that the tracker works no matter
+// which directory it's launched from.
+const EMBEDDED_FILES: [(&str, &str); 8] = [
+    (FILE_NAME_ALIASES, include_str!("../../Breath of the Wild Aliases.txt")),
+    (FILE_NAME_CHARACTERS, include_str!("../../Breath of the Wild Characters.txt")),
+    (FILE_NAME_DOG_TREASURES, include_str!("../../Breath of the Wild Dog Treasures.txt")),
+    (FILE_NAME_ITEMS, include_str!("../../Breath of the Wild Items.txt")),
+    (FILE_NAME_LOCATIONS, include_str!("../../Breath of the Wild Locations.txt")),
+    (FILE_NAME_MAP_LOCATIONS, include_str!("../../Breath of the Wild Map Locations for Completion Raw.txt")),
+    (FILE_NAME_QUESTS, include_str!("../../Breath of the Wild Quests.txt")),
+    (FILE_NAME_SHRINES, include_str!("../../Breath of the Wild Shrines.txt")),
+];
+
+#[derive(Clone, Debug)]
+pub enum DataSource {
+    Directory {
+        path: PathBuf,
+    },
+    Embedded,
+}
+
+impl DataSource {
+    pub fn new_directory<P: AsRef<Path>>(path: P) -> Self {
+        Self::Directory {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn new_embedded() -> Self {
+        Self::Embedded
+    }
+
+    pub fn from_env() -> Option<Self> {
+        env::var(ENV_DATA_PATH).ok().map(Self::new_directory)
+    }
+
+    pub fn from_env_or_directory<P: AsRef<Path>>(path: P) -> Self {
+        Self::from_env().unwrap_or_else(|| Self::new_directory(path))
+    }
+
+    // Use the directory named by the environment variable if there is one, otherwise the current
+    // directory if it has the catalog files, otherwise the copies built into the binary.
+    pub fn new_default() -> Self {
+        if let Some(source) = Self::from_env() {
+            return source;
+        }
+        if Path::new(FILE_NAME_CHARACTERS).exists() {
+            return Self::new_directory(".");
+        }
+        Self::new_embedded()
+    }
+
+    pub fn read_to_string(&self, file_name: &str) -> io::Result<String> {
+        match self {
+            DataSource::Directory { path } => fs::read_to_string(path.join(file_name)),
+            DataSource::Embedded => {
+                EMBEDDED_FILES
+                    .iter()
+                    .find(|(embedded_name, _)| *embedded_name == file_name)
+                    .map(|(_, content)| content.to_string())
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("\"{}\" is not embedded", file_name)))
+            }
+        }
+    }
+
+    pub fn has_file(&self, file_name: &str) -> bool {
+        match self {
+            DataSource::Directory { path } => path.join(file_name).exists(),
+            DataSource::Embedded => EMBEDDED_FILES.iter().any(|(embedded_name, _)| *embedded_name == file_name),
+        }
+    }
+
+    // When the file was last changed, if it's a file on disk. The embedded files never change.
+    pub fn modified(&self, file_name: &str) -> Option<SystemTime> {
+        match self {
+            DataSource::Directory { path } => fs::metadata(path.join(file_name)).and_then(|metadata| metadata.modified()).ok(),
+            DataSource::Embedded => None,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            DataSource::Directory { path } => format!("directory \"{}\"", path.display()),
+            DataSource::Embedded => "embedded catalog".to_string(),
+        }
+    }
+}
+
+// The names of the text catalog files, which are the ones built into the binary.
+pub fn text_file_names() -> impl Iterator<Item = &'static str> {
+    EMBEDDED_FILES.iter().map(|(file_name, _)| *file_name)
+}
+
+// Copies the shipped catalog files into a fresh directory under the system temp directory so
+// that a test can change them without touching the real ones.
+#[cfg(test)]
+pub fn copy_embedded_files_to_temp(dir_name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("botw_{}_{}", dir_name, std::process::id()));
+    if path.exists() {
+        fs::remove_dir_all(&path).unwrap();
+    }
+    fs::create_dir_all(&path).unwrap();
+    for (file_name, content) in EMBEDDED_FILES.iter() {
+        fs::write(path.join(file_name), content).unwrap();
+    }
+    path
+}
@@ -3,7 +3,10 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::{thread, time};
 
+use super::alias::AliasList;
+use super::lookup::LookupError;
 use super::model::*;
+use super::parse;
 use super::runtime::GameClock;
 use util::format;
 
@@ -13,6 +16,10 @@ pub const NULL_TIME: usize = usize::MAX;
 pub struct GameRecord {
     pub name: String,
     pub events: Vec<GameEvent>,
+    // Groups of events that were undone, with the most recently undone group last. Adding a new
+    // event clears it.
+    #[serde(skip)]
+    pub undone_events: Vec<Vec<GameEvent>>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -21,11 +28,19 @@ pub struct GameEvent {
     typ: GameEventType,
     name: String,
     number: Option<usize>,
+    // The value the event replaced when it was applied: the old count for events like SetHearts
+    // and KorokSeed, or the old time for events like DiscoverLocation. Undoing the event puts it
+    // back.
     previous_number: Option<usize>,
+    // Whether add_event() generated this event because the event after it needed it, as when
+    // completing a shrine that hadn't been started. It's undone along with that event.
+    #[serde(default)]
+    predecessor: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum GameEventType {
+    ActivateTower,
     AddToCompendium,
     BloodMoon,
     CharacterDeath,
@@ -58,35 +73,86 @@ impl GameRecord {
         Self {
             name: name.to_string(),
             events: vec![],
+            undone_events: vec![],
         }
     }
 
-    pub fn add_event(&mut self, model: &mut Model, event: GameEvent) {
-        //bg!(&event);
-        let mut events: Vec<GameEvent> = vec![];
-        events.push(event.clone());
-        let mut current_event = event;
-        // let mut previous_event_string = event.to_simple_text();
-        loop {
-            let current_event_opt = current_event.gen_predecessor(model);
-            //bg!(&current_event);
-            match current_event_opt {
-                Some(this_event) => {
-                    //assert_ne!(event.to_simple_text(), previous_event_string);
-                    //previous_event_string = event.to_simple_text();
-                    events.push(this_event.clone());
-                    current_event = this_event;
-                },
-                None => {
-                    break;
-                }
-            }
+    // Adds the event along with any events that have to come before it. If any of them refers to
+    // something that isn't in the model, nothing is applied or recorded.
+    pub fn add_event(&mut self, model: &mut Model, event: GameEvent) -> Result<(), LookupError> {
+        let mut events = event.expand(model)?;
+        for one_event in events.iter() {
+            one_event.check_target(model)?;
         }
-        events.reverse();
         for one_event in events.iter_mut() {
-            one_event.apply(model);
+            one_event.apply(model)?;
         }
         self.events.append(&mut events);
+        self.undone_events.clear();
+        Ok(())
+    }
+
+    // Takes back the last event added along with any predecessor events that were added with it,
+    // returning them in the order they were originally applied. Returns an empty list if there's
+    // nothing to undo. If any of the events was never applied, as in a record that was read in
+    // without replaying it, nothing is undone.
+    pub fn undo(&mut self, model: &mut Model) -> Result<Vec<GameEvent>, LookupError> {
+        let events = self.move_last_group_to_undone();
+        if let Err(e) = events.iter().try_for_each(GameEvent::check_applied) {
+            self.move_last_undone_group_back();
+            return Err(e);
+        }
+        for event in events.iter().rev() {
+            event.unapply(model)?;
+        }
+        Ok(events)
+    }
+
+    // The bookkeeping half of undo() for when there's no model to change, as when reading the
+    // record back from a journal.
+    pub fn move_last_group_to_undone(&mut self) -> Vec<GameEvent> {
+        let mut group_start = self.events.len().saturating_sub(1);
+        while group_start > 0 && self.events[group_start - 1].predecessor {
+            group_start -= 1;
+        }
+        let events = self.events.split_off(group_start);
+        if !events.is_empty() {
+            self.undone_events.push(events.clone());
+        }
+        events
+    }
+
+    // The bookkeeping half of redo().
+    pub fn move_last_undone_group_back(&mut self) -> Vec<GameEvent> {
+        let events = self.undone_events.pop().unwrap_or_default();
+        self.events.extend(events.iter().cloned());
+        events
+    }
+
+    // Applies the most recently undone group of events again. As with add_event(), if any of them
+    // refers to something that isn't in the model, nothing is applied and the group stays undone.
+    pub fn redo(&mut self, model: &mut Model) -> Result<Vec<GameEvent>, LookupError> {
+        let mut events = match self.undone_events.pop() {
+            Some(events) => events,
+            None => return Ok(vec![]),
+        };
+        if let Err(e) = events.iter().try_for_each(|event| event.check_target(model)) {
+            self.undone_events.push(events);
+            return Err(e);
+        }
+        for event in events.iter_mut() {
+            event.apply(model)?;
+        }
+        self.events.extend(events.iter().cloned());
+        Ok(events)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.events.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone_events.is_empty()
     }
 
     pub fn review(&mut self, event_count: usize) {
@@ -116,147 +182,192 @@ impl GameEvent {
             name: name.to_string(),
             number,
             previous_number: None,
+            predecessor: false,
         }
     }
 
+    // An event that add_event() adds because another event needs it.
+    pub fn new_predecessor(time: usize, typ: GameEventType, name: &str, number: Option<usize>) -> Self {
+        let mut event = Self::new(time, typ, name, number);
+        event.predecessor = true;
+        event
+    }
+
     pub fn to_simple_text(&self) -> String {
         let number = self.number.map_or("None".to_string(), |x| x.to_string());
         let previous_number = self.previous_number.map_or("None".to_string(), |x| x.to_string());
         format!("{}\t{}\t{}\t{}\t{}", self.time, self.typ.variant_to_string(), self.name, number, previous_number)
     }
 
-    pub fn gen_predecessor(&self, model: &Model) -> Option<GameEvent> {
-        //bg!(&self);
-        let GameEvent { time, typ, name, .. } = self;
-        match typ {
-            GameEventType::CompleteQuest => {
-                if !model.get_quest(name).is_started() {
-                    Some(GameEvent::new(*time, GameEventType::StartQuest, name, None))
-                } else {
-                    None
-                }
-            },
-            GameEventType::CompleteShrine => {
-                //bg!(model.get_shrine(name));
-                //bg!(model.get_shrine(name).is_started());
-                if !model.get_shrine(name).is_started() {
-                    Some(GameEvent::new(*time, GameEventType::StartShrine, name, None))
-                } else {
-                    None
-                }
-            },
-            GameEventType::DiscoverLocation => {
-                let parent_location = model.get_parent_location(name);
-                //bg!(&parent_location);
-                match parent_location {
-                    Some(parent_location) => {
-                        if !parent_location.is_discovered() {
-                            Some(GameEvent::new(*time, GameEventType::DiscoverLocation, &parent_location.name, None))
-                        } else {
-                            None
-                        }
-                    },
-                    None => None
-                }
-            },
-            GameEventType::FindDogTreasure | GameEventType::LightFlame => {
-                if !model.get_location(name).is_discovered() {
-                    Some(GameEvent::new(*time, GameEventType::DiscoverLocation, name, None))
-                } else {
-                    None
-                }
-            },
-            GameEventType::StartShrine => {
-                let quest_name = &model.get_location(name).quest;
-                match quest_name {
-                    Some(quest_name) => {
-                        if !model.get_quest(&quest_name).is_completed() {
-                            Some(GameEvent::new(*time, GameEventType::CompleteQuest, &quest_name, None))
-                        } else {
-                            None
-                        }
-                    },
-                    None => None
-                }
-            },
-            _ => None
+    pub fn time(&self) -> usize {
+        self.time
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn number(&self) -> Option<usize> {
+        self.number
+    }
+
+    pub fn typ(&self) -> &GameEventType {
+        &self.typ
+    }
+
+    pub fn is_predecessor(&self) -> bool {
+        self.predecessor
+    }
+
+    // The event's number for display, or "?" if it's missing.
+    fn number_label(&self) -> String {
+        self.number.map_or("?".to_string(), |number| number.to_string())
+    }
+
+    // Like "from 3 to 4", leaving out the old value if the event hasn't been applied yet.
+    fn change_label(&self) -> String {
+        let number = self.number_label();
+        match self.previous_number {
+            Some(previous_number) => format!("from {} to {}", previous_number, number),
+            None => format!("to {}", number),
         }
     }
 
-    pub fn apply(&mut self, model: &mut Model) {
-        let GameEvent { time, typ, name, number, ref mut previous_number } = self;
-        match typ {
-            GameEventType::BloodMoon => {
-                model.blood_moons += 1;
-            },
-            GameEventType::CompleteQuest => {
-                model.get_quest_mut(name).completed_time = *time;
-            },
-            GameEventType::CompleteShrine => {
-                model.get_shrine_mut(name).completed_time = *time;
-            },
-            GameEventType::DiscoverLocation => {
-                model.get_location_mut(name).discovered_time = *time;
-            },
-            GameEventType::FindDogTreasure => {
-                model.get_location_mut(name).dog_treasure_found_time = *time;
-            },
-            GameEventType::KorokSeed => {
-                model.korok_seeds += number.unwrap();
-            },
-            GameEventType::LightFlame => {
-                model.get_location_mut(name).flame_lit_time = *time;
-            },
-            GameEventType::LinkDeath => {
-                model.deaths += 1;
-            },
-            GameEventType::MeetCharacter => {
-                model.get_character_mut(name).met_time = *time;
-            },
-            GameEventType::MeetCharacterFlashback => {
-                model.get_character_mut(name).met_in_flashback_time = *time;
-            },
-            GameEventType::MentionCharacter => {
-                model.get_character_mut(name).mentioned_time = *time;
-            },
-            GameEventType::OpenChest => {
-                model.chests += number.unwrap();
-            },
-            GameEventType::SetBowSlots => {
-                *previous_number = Some(model.bow_slots);
-                model.bow_slots = number.unwrap();
-            },
-            GameEventType::SetHearts => {
-                *previous_number = Some(model.hearts);
-                model.hearts = number.unwrap();
-            },
-            GameEventType::SetShieldSlots => {
-                *previous_number = Some(model.shield_slots);
-                model.shield_slots = number.unwrap();
-            },
-            GameEventType::SetStamina => {
-                *previous_number = Some(model.stamina);
-                model.stamina = number.unwrap();
-            },
-            GameEventType::SetWeaponSlots => {
-                *previous_number = Some(model.weapon_slots);
-                model.weapon_slots = number.unwrap();
-            },
-            GameEventType::StartQuest => {
-                model.get_quest_mut(name).started_time = *time;
-            },
-            GameEventType::StartShrine => {
-                model.get_shrine_mut(name).started_time = *time;
+    // The list in the model that this event's name refers to, or None for events like KorokSeed
+    // that don't have a target.
+    pub fn target_list(&self) -> Option<AliasList> {
+        match self.typ {
+            GameEventType::CharacterDeath | GameEventType::MeetCharacter | GameEventType::MeetCharacterFlashback
+                | GameEventType::MentionCharacter => Some(AliasList::Character),
+            GameEventType::ActivateTower | GameEventType::CompleteShrine | GameEventType::DiscoverLocation
+                | GameEventType::FindDogTreasure | GameEventType::LightFlame | GameEventType::StartShrine => Some(AliasList::Location),
+            GameEventType::CompleteQuest | GameEventType::StartQuest => Some(AliasList::Quest),
+            GameEventType::AddToCompendium | GameEventType::IdentifyItem | GameEventType::SetArmorLevel
+                | GameEventType::SetItemCount => Some(AliasList::Item),
+            _ => None,
+        }
+    }
+
+    // Whether the event's target is in the model and of the right type, which may not be the case
+    // if the catalog has changed since the event was recorded. A counter or setting event has no
+    // target but needs a number.
+    pub fn check_target(&self, model: &Model) -> Result<(), LookupError> {
+        if self.is_count() && self.number.is_none() {
+            return Err(self.missing_number_error());
+        }
+        match self.typ {
+            GameEventType::ActivateTower => model.try_get_tower(&self.name).map(|_| ()),
+            GameEventType::CompleteShrine | GameEventType::StartShrine => model.try_get_shrine(&self.name).map(|_| ()),
+            GameEventType::SetArmorLevel => model.try_get_armor(&self.name).map(|_| ()),
+            _ => match self.target_list() {
+                Some(AliasList::Character) => model.try_character_id(&self.name).map(|_| ()),
+                Some(AliasList::Location) => model.try_location_id(&self.name).map(|_| ()),
+                Some(AliasList::Quest) => model.try_quest_id(&self.name).map(|_| ()),
+                Some(AliasList::Item) => model.try_item_id(&self.name).map(|_| ()),
+                None => Ok(()),
             },
-            _ => unimplemented!("{:?}: {}: {:?}", &typ, &name, number)
         }
     }
 
+    pub fn target_exists(&self, model: &Model) -> bool {
+        self.check_target(model).is_ok()
+    }
+
+    // Changes the model to reflect the event. Nothing is changed if the event's target can't be
+    // found or if it's a counter or setting without a number.
+    pub fn apply(&mut self, model: &mut Model) -> Result<(), LookupError> {
+        // The counters and settings take the event's number, which is the new total rather than
+        // the amount to add. Everything else has a target and takes the event's time.
+        //
+        // KorokSeed and OpenChest used to add their number to the count, and BloodMoon and
+        // LinkDeath added one. They now set the count like the other counters so that undo can
+        // put back the value they replaced. The commands already gave the new total, and the
+        // event text already read "Korok seeds to 12".
+        let new_value = if self.is_count() {
+            self.number.ok_or_else(|| self.missing_number_error())?
+        } else {
+            self.time
+        };
+        let value = self.target_value(model)?;
+        self.previous_number = Some(*value);
+        *value = new_value;
+        Ok(())
+    }
+
+    // Puts back the value the event replaced when it was applied. An event whose target is no
+    // longer in the model has nothing to put back.
+    pub fn unapply(&self, model: &mut Model) -> Result<(), LookupError> {
+        self.check_applied()?;
+        if let (Some(previous_number), Ok(value)) = (self.previous_number, self.target_value(model)) {
+            *value = previous_number;
+        }
+        Ok(())
+    }
+
+    // Whether the event remembers the value it replaced, which it needs in order to be undone.
+    pub fn check_applied(&self) -> Result<(), LookupError> {
+        match self.previous_number {
+            Some(_) => Ok(()),
+            None => Err(LookupError::NotApplied { event: self.to_string() }),
+        }
+    }
+
+    fn missing_number_error(&self) -> LookupError {
+        LookupError::MissingNumber { event_type: self.typ.variant_to_string().to_string() }
+    }
+
+    // Whether the event sets a counter or setting to its number rather than a time to its time.
+    pub fn is_count(&self) -> bool {
+        match self.typ {
+            GameEventType::SetArmorLevel | GameEventType::SetItemCount => true,
+            _ => self.target_list().is_none(),
+        }
+    }
+
+    // Counters that only go up over a playthrough, unlike settings such as the number of hearts.
+    pub fn is_increasing_count(&self) -> bool {
+        matches!(self.typ, GameEventType::BloodMoon | GameEventType::KorokSeed | GameEventType::LinkDeath | GameEventType::OpenChest
+            | GameEventType::SetArmorLevel)
+    }
+
+    // The counter or time in the model that the event changes.
+    pub fn target_value<'a>(&self, model: &'a mut Model) -> Result<&'a mut usize, LookupError> {
+        let GameEvent { typ, name, .. } = self;
+        Ok(match typ {
+            GameEventType::ActivateTower => &mut model.try_get_tower_mut(name)?.activated_time,
+            GameEventType::AddToCompendium => &mut model.try_get_item_mut(name)?.added_to_compendium_time,
+            GameEventType::BloodMoon => &mut model.blood_moons,
+            GameEventType::CharacterDeath => &mut model.try_get_character_mut(name)?.died_time,
+            GameEventType::CompleteQuest => &mut model.try_get_quest_mut(name)?.completed_time,
+            GameEventType::CompleteShrine => &mut model.try_get_shrine_mut(name)?.completed_time,
+            GameEventType::DiscoverLocation => &mut model.try_get_location_mut(name)?.discovered_time,
+            GameEventType::FindDogTreasure => &mut model.try_get_location_mut(name)?.dog_treasure_found_time,
+            GameEventType::IdentifyItem => &mut model.try_get_item_mut(name)?.identified_time,
+            GameEventType::KorokSeed => &mut model.korok_seeds,
+            GameEventType::LightFlame => &mut model.try_get_location_mut(name)?.flame_lit_time,
+            GameEventType::LinkDeath => &mut model.deaths,
+            GameEventType::MeetCharacter => &mut model.try_get_character_mut(name)?.met_time,
+            GameEventType::MeetCharacterFlashback => &mut model.try_get_character_mut(name)?.met_in_flashback_time,
+            GameEventType::MentionCharacter => &mut model.try_get_character_mut(name)?.mentioned_time,
+            GameEventType::OpenChest => &mut model.chests,
+            GameEventType::SetArmorLevel => &mut model.try_get_armor_mut(name)?.armor_level,
+            GameEventType::SetBowSlots => &mut model.bow_slots,
+            GameEventType::SetHearts => &mut model.hearts,
+            GameEventType::SetItemCount => &mut model.try_get_item_mut(name)?.count,
+            GameEventType::SetShieldSlots => &mut model.shield_slots,
+            GameEventType::SetStamina => &mut model.stamina,
+            GameEventType::SetWeaponSlots => &mut model.weapon_slots,
+            GameEventType::StartQuest => &mut model.try_get_quest_mut(name)?.started_time,
+            GameEventType::StartShrine => &mut model.try_get_shrine_mut(name)?.started_time,
+        })
+    }
+
 }
 
 impl fmt::Display for GameEvent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let type_details = match self.typ {
+            GameEventType::ActivateTower => format!("Activated {}.", self.name),
             GameEventType::AddToCompendium => format!("Added {} to compendium.", self.name),
             GameEventType::BloodMoon => "Blood moon.".to_string(),
             GameEventType::CharacterDeath => format!("{} died.", self.name),
@@ -264,20 +375,20 @@ impl fmt::Display for GameEvent {
             GameEventType::DiscoverLocation => format!("Discovered {}.", self.name),
             GameEventType::FindDogTreasure => format!("Found dog treasure at {}.", self.name),
             GameEventType::IdentifyItem => format!("Identified {}.", self.name),
-            GameEventType::KorokSeed => format!("Korok seeds to {}.", self.number.unwrap()),
+            GameEventType::KorokSeed => format!("Korok seeds to {}.", self.number_label()),
             GameEventType::LightFlame => format!("Lit flame at {}.", self.name),
-            GameEventType::LinkDeath => format!("Link deaths to {}.", self.number.unwrap()),
+            GameEventType::LinkDeath => format!("Link deaths to {}.", self.number_label()),
             GameEventType::MeetCharacter => format!("Met {}.", self.name),
             GameEventType::MeetCharacterFlashback => format!("Met {} in a flashback.", self.name),
             GameEventType::MentionCharacter => format!("Mentioned {}.", self.name),
-            GameEventType::OpenChest => format!("Opened chests to {}.", self.number.unwrap()),
-            GameEventType::SetArmorLevel => format!("Changed {} from {} to {}.", self.name, self.previous_number.unwrap(), self.number.unwrap()),
-            GameEventType::SetBowSlots => format!("Bow slots to {}.", self.number.unwrap()),
-            GameEventType::SetHearts => format!("Changed hearts from {} to {}.", self.previous_number.unwrap(), self.number.unwrap()),
-            GameEventType::SetItemCount => format!("Changed the count for {} from {} to {}.", self.name, self.previous_number.unwrap(), self.number.unwrap()),
-            GameEventType::SetShieldSlots => format!("Shield slots to {}.", self.number.unwrap()),
-            GameEventType::SetStamina => format!("Changed stamina from {} to {}.", self.previous_number.unwrap(), self.number.unwrap()),
-            GameEventType::SetWeaponSlots => format!("Weapon slots to {}.", self.number.unwrap()),
+            GameEventType::OpenChest => format!("Opened chests to {}.", self.number_label()),
+            GameEventType::SetArmorLevel => format!("Changed {} {}.", self.name, self.change_label()),
+            GameEventType::SetBowSlots => format!("Bow slots to {}.", self.number_label()),
+            GameEventType::SetHearts => format!("Changed hearts {}.", self.change_label()),
+            GameEventType::SetItemCount => format!("Changed the count for {} {}.", self.name, self.change_label()),
+            GameEventType::SetShieldSlots => format!("Shield slots to {}.", self.number_label()),
+            GameEventType::SetStamina => format!("Changed stamina {}.", self.change_label()),
+            GameEventType::SetWeaponSlots => format!("Weapon slots to {}.", self.number_label()),
             GameEventType::StartQuest | GameEventType::StartShrine => format!("Started {}.", self.name),
         };
         let s = format!("{:?}: {}", GameClock::format_time(self.time), type_details);
@@ -288,6 +399,7 @@ impl fmt::Display for GameEvent {
 impl GameEventType {
     pub fn variant_to_string(&self) -> &str {
         match self {
+            GameEventType::ActivateTower => "ActivateTower",
             GameEventType::AddToCompendium => "AddToCompendium",
             GameEventType::BloodMoon => "BloodMoon",
             GameEventType::CharacterDeath => "CharacterDeath",
@@ -317,6 +429,7 @@ impl GameEventType {
 
     pub fn string_to_variant(s: &str) -> Self {
         match s {
+            "ActivateTower" => GameEventType::ActivateTower,
             "AddToCompendium" => GameEventType::AddToCompendium,
             "BloodMoon" => GameEventType::BloodMoon,
             "CharacterDeath" => GameEventType::CharacterDeath,
@@ -347,11 +460,17 @@ impl GameEventType {
 }
 
 pub fn try_create_events() {
-    let mut model = Model::new();
+    let mut model = match Model::new() {
+        Ok(model) => model,
+        Err(errors) => {
+            parse::print_errors(&errors);
+            return;
+        }
+    };
     let mut game_record = GameRecord::new("Test");
     let clock = GameClock::new_running(1_000);
 
-    game_record.add_event(&mut model,GameEvent::new(clock.time(), GameEventType::DiscoverLocation, "Phalian Highlands", None));
+    game_record.add_event(&mut model,GameEvent::new(clock.time(), GameEventType::DiscoverLocation, "Phalian Highlands", None)).unwrap();
 
     // clock.add_seconds(1234);
 
@@ -359,18 +478,77 @@ pub fn try_create_events() {
 
     thread::sleep(time::Duration::from_secs(3));
 
-    // Complete a shrine that has not been started and which has a quest that has not been started.
-    // This should result in four events: start quest, complete quest, start shrine, complete shrine.
-    game_record.add_event(&mut model, GameEvent::new(clock.time(), GameEventType::CompleteShrine, "Mezza Lo Shrine", None));
-
-    // TO DO: Consider handling the case where there are multiple predecessor events. For instance,
-    // starting a shrine means the location must be discovered but also if there's a quest it must
-    // be completed. Maybe the predecessor function should return an array.
+    // Complete a shrine that has not been discovered or started and which has a quest that has not
+    // been started. This should result in five events: discover shrine, start quest, complete
+    // quest, start shrine, complete shrine.
+    game_record.add_event(&mut model, GameEvent::new(clock.time(), GameEventType::CompleteShrine, "Mezza Lo Shrine", None)).unwrap();
 
     game_record.print_events_serialized();
 
+    // Undoing the shrine should take back all five events, and redoing it should put them back.
+    let undone_events = game_record.undo(&mut model).unwrap();
+    assert_eq!(5, undone_events.len());
+    assert!(!model.get_shrine("Mezza Lo Shrine").is_started());
+    game_record.redo(&mut model).unwrap();
+    assert!(model.get_shrine("Mezza Lo Shrine").is_completed());
+
     let json = serde_json::to_string(&game_record).unwrap();
     let new_game_record: GameRecord = serde_json::from_str(&json).unwrap();
     new_game_record.print_events_serialized();
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::data_source::DataSource;
+
+    #[test]
+    fn count_event_without_a_number_is_not_applied() {
+        let mut model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        let mut event = GameEvent::new(0, GameEventType::KorokSeed, "", None);
+        assert!(matches!(event.apply(&mut model), Err(LookupError::MissingNumber { .. })));
+        assert_eq!(0, model.korok_seeds);
+        assert!(event.to_string().ends_with("Korok seeds to ?."));
+    }
+
+    #[test]
+    fn undo_and_redo_take_the_predecessors_along() {
+        let mut model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        let mut game_record = GameRecord::new("Test");
+        game_record.add_event(&mut model, GameEvent::new(10, GameEventType::KorokSeed, "", Some(12))).unwrap();
+        game_record.add_event(&mut model, GameEvent::new(20, GameEventType::CompleteShrine, "Mezza Lo Shrine", None)).unwrap();
+        let group_len = game_record.events.len() - 1;
+        assert!(group_len > 1);
+        assert!(game_record.events[1..group_len].iter().all(GameEvent::is_predecessor));
+
+        let undone_events = game_record.undo(&mut model).unwrap();
+        assert_eq!(group_len, undone_events.len());
+        assert_eq!(1, game_record.events.len());
+        assert!(!model.get_shrine("Mezza Lo Shrine").is_discovered());
+        assert!(!model.get_shrine("Mezza Lo Shrine").is_started());
+
+        let redone_events = game_record.redo(&mut model).unwrap();
+        assert_eq!(group_len, redone_events.len());
+        assert!(model.get_shrine("Mezza Lo Shrine").is_completed());
+        assert!(!game_record.can_redo());
+
+        // Undoing both groups puts the counter back to where it started.
+        game_record.undo(&mut model).unwrap();
+        assert_eq!(1, game_record.events.len());
+        assert_eq!(12, model.korok_seeds);
+        game_record.undo(&mut model).unwrap();
+        assert_eq!(0, model.korok_seeds);
+        assert!(game_record.undo(&mut model).unwrap().is_empty());
+    }
+
+    #[test]
+    fn event_that_was_never_applied_is_not_undone() {
+        let mut model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        let mut game_record = GameRecord::new("Test");
+        game_record.events.push(GameEvent::new(10, GameEventType::KorokSeed, "", Some(12)));
+        assert!(matches!(game_record.undo(&mut model), Err(LookupError::NotApplied { .. })));
+        assert_eq!(1, game_record.events.len());
+        assert!(!game_record.can_redo());
+    }
+}
@@ -0,0 +1,104 @@
+use super::arena::LocationId;
+use super::model::*;
+
+// Walking the Region -> Area -> location tree. Each location has its parent in parent_location
+// and its children in child_locations, in the order they were loaded. The functions here take
+// IDs since they're mostly used by code that has already looked up the location.
+
+// The parent, the parent's parent, and so on up to the region.
+pub struct Ancestors<'a> {
+    model: &'a Model,
+    next: Option<LocationId>,
+}
+
+// A preorder walk with the depth of each location, where the starting locations are at depth 0.
+pub struct DepthFirst<'a> {
+    model: &'a Model,
+    // Locations still to visit, with the next one at the end.
+    stack: Vec<(LocationId, usize)>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = LocationId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        self.next = self.model.locations.by_id(id).parent_location;
+        Some(id)
+    }
+}
+
+impl<'a> Iterator for DepthFirst<'a> {
+    type Item = (LocationId, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (id, depth) = self.stack.pop()?;
+        let children = &self.model.locations.by_id(id).child_locations;
+        self.stack.extend(children.iter().rev().map(|child_id| (*child_id, depth + 1)));
+        Some((id, depth))
+    }
+}
+
+impl Model {
+    // Makes the location a child of the parent, updating the links in both directions. Nothing is
+    // changed if the link would make a loop, and the returned error is what was expected instead.
+    pub fn set_parent_location(&mut self, id: LocationId, parent_id: LocationId) -> Result<(), String> {
+        if id == parent_id {
+            return Err("a parent other than the location itself".to_string());
+        }
+        if self.location_is_within(parent_id, id) {
+            return Err(format!("a parent that is not under this location, unlike \"{}\"", self.location_name(parent_id)));
+        }
+        if let Some(old_parent_id) = self.locations.by_id(id).parent_location {
+            self.locations.by_id_mut(old_parent_id).child_locations.retain(|child_id| *child_id != id);
+        }
+        self.locations.by_id_mut(id).parent_location = Some(parent_id);
+        self.locations.by_id_mut(parent_id).add_child_location(id);
+        Ok(())
+    }
+
+    // The locations with no parent, which are normally the regions, in name order.
+    pub fn root_locations(&self) -> Vec<LocationId> {
+        self.locations
+            .iter_ids()
+            .filter(|(_, location)| location.parent_location.is_none())
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    pub fn ancestors(&self, id: LocationId) -> Ancestors<'_> {
+        Ancestors {
+            model: self,
+            next: self.locations.by_id(id).parent_location,
+        }
+    }
+
+    // Everything under the location, not counting the location itself.
+    pub fn descendants(&self, id: LocationId) -> impl Iterator<Item = LocationId> + '_ {
+        self.walk_depth_first(id).skip(1).map(|(id, _)| id)
+    }
+
+    // The other locations with the same parent. For a region these are the other regions.
+    pub fn siblings(&self, id: LocationId) -> Vec<LocationId> {
+        let candidates = match self.locations.by_id(id).parent_location {
+            Some(parent_id) => self.locations.by_id(parent_id).child_locations.clone(),
+            None => self.root_locations(),
+        };
+        candidates.into_iter().filter(|sibling_id| *sibling_id != id).collect()
+    }
+
+    pub fn walk_depth_first(&self, id: LocationId) -> DepthFirst<'_> {
+        DepthFirst {
+            model: self,
+            stack: vec![(id, 0)],
+        }
+    }
+
+    // The whole tree, one region after another.
+    pub fn walk_all_depth_first(&self) -> DepthFirst<'_> {
+        DepthFirst {
+            model: self,
+            stack: self.root_locations().into_iter().rev().map(|id| (id, 0)).collect(),
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use super::game_record::{GameEvent, GameRecord};
+use super::lookup::LookupError;
+use super::model::*;
+
+// A game record kept on disk as it's played so that a crash loses at most the event that was
+// being written. The journal is a text file with one JSON entry per line. Each event is appended
+// and flushed to disk as soon as it's added, and undo and redo are entries of their own so the
+// file is only ever appended to.
+//
+// Every so often the whole file is replaced by a single snapshot of the record, written to a
+// temporary file first and then renamed over the journal so that there's always a complete copy
+// on disk. Recovering the record means starting from the last snapshot and applying the entries
+// after it. If the program died partway through writing the last line, that line is dropped and
+// cut off the end of the file.
+
+pub const JOURNAL_FILE_EXTENSION: &str = "journal";
+// The number of entries after the last snapshot that triggers a new one.
+pub const SNAPSHOT_INTERVAL: usize = 500;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum JournalEntry {
+    Snapshot {
+        record: GameRecord,
+        // The record doesn't serialize its undone events, but a redo after the snapshot needs
+        // them.
+        undone_events: Vec<Vec<GameEvent>>,
+    },
+    Event(GameEvent),
+    Undo,
+    Redo,
+}
+
+#[derive(Debug)]
+pub enum JournalError {
+    Lookup(LookupError),
+    Io(io::Error),
+}
+
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    entries_since_snapshot: usize,
+}
+
+// What was found when the journal was opened.
+#[derive(Debug)]
+pub struct Recovery {
+    pub record: GameRecord,
+    // The entries applied on top of the last snapshot.
+    pub entry_count: usize,
+    // The incomplete last line, if there was one.
+    pub dropped_line: Option<String>,
+}
+
+impl Journal {
+    // Starts a new journal at the path, replacing any file that's there, with a snapshot of the
+    // record as its first line.
+    pub fn create<P: AsRef<Path>>(path: P, record: &GameRecord) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = write_snapshot(&path, record)?;
+        Ok(Self {
+            path,
+            file,
+            entries_since_snapshot: 0,
+        })
+    }
+
+    // Opens an existing journal and reads back the record from it. New entries are appended after
+    // the ones already there.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<(Self, Recovery)> {
+        let path = path.as_ref().to_path_buf();
+        let recovery = recover(&path)?;
+        let file = OpenOptions::new().append(true).open(&path)?;
+        let journal = Self {
+            path,
+            file,
+            entries_since_snapshot: recovery.entry_count,
+        };
+        Ok((journal, recovery))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Adds the event and any events it needs to the record and then writes each of them to the
+    // journal. Nothing is written if the record doesn't take the event.
+    pub fn add_event(&mut self, record: &mut GameRecord, model: &mut Model, event: GameEvent) -> Result<(), JournalError> {
+        let first_new_index = record.events.len();
+        record.add_event(model, event).map_err(JournalError::Lookup)?;
+        for event in record.events[first_new_index..].iter() {
+            self.append(&JournalEntry::Event(event.clone()))?;
+        }
+        self.snapshot_if_due(record)?;
+        Ok(())
+    }
+
+    pub fn undo(&mut self, record: &mut GameRecord, model: &mut Model) -> Result<Vec<GameEvent>, JournalError> {
+        let events = record.undo(model).map_err(JournalError::Lookup)?;
+        if !events.is_empty() {
+            self.append(&JournalEntry::Undo)?;
+            self.snapshot_if_due(record)?;
+        }
+        Ok(events)
+    }
+
+    pub fn redo(&mut self, record: &mut GameRecord, model: &mut Model) -> Result<Vec<GameEvent>, JournalError> {
+        let events = record.redo(model).map_err(JournalError::Lookup)?;
+        if !events.is_empty() {
+            self.append(&JournalEntry::Redo)?;
+            self.snapshot_if_due(record)?;
+        }
+        Ok(events)
+    }
+
+    // Replaces everything in the journal with a single snapshot of the record.
+    pub fn snapshot(&mut self, record: &GameRecord) -> io::Result<()> {
+        self.file = write_snapshot(&self.path, record)?;
+        self.entries_since_snapshot = 0;
+        Ok(())
+    }
+
+    fn snapshot_if_due(&mut self, record: &GameRecord) -> io::Result<()> {
+        if self.entries_since_snapshot >= SNAPSHOT_INTERVAL {
+            self.snapshot(record)?;
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, entry: &JournalEntry) -> io::Result<()> {
+        // The line and its newline go out in one write so that a crash leaves at most one partial
+        // line at the end of the file.
+        self.file.write_all(entry_to_line(entry)?.as_bytes())?;
+        self.file.sync_data()?;
+        self.entries_since_snapshot += 1;
+        Ok(())
+    }
+}
+
+// Returns the journal file opened for appending after the snapshot.
+fn write_snapshot(path: &Path, record: &GameRecord) -> io::Result<File> {
+    let entry = JournalEntry::Snapshot {
+        record: GameRecord {
+            name: record.name.clone(),
+            events: record.events.clone(),
+            undone_events: vec![],
+        },
+        undone_events: record.undone_events.clone(),
+    };
+    let temp_path = path.with_extension(format!("{}.tmp", JOURNAL_FILE_EXTENSION));
+    {
+        let mut temp_file = File::create(&temp_path)?;
+        temp_file.write_all(entry_to_line(&entry)?.as_bytes())?;
+        temp_file.sync_all()?;
+    }
+    fs::rename(&temp_path, path)?;
+    OpenOptions::new().append(true).open(path)
+}
+
+fn entry_to_line(entry: &JournalEntry) -> io::Result<String> {
+    let json = serde_json::to_string(entry).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(format!("{}\n", json))
+}
+
+// Reads the record back from the journal without changing anything except cutting off an
+// incomplete last line. A bad line anywhere else means the file was damaged some other way, so
+// it's an error rather than something to skip.
+pub fn recover<P: AsRef<Path>>(path: P) -> io::Result<Recovery> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)?;
+    let mut entries = vec![];
+    let mut dropped_line = None;
+    let mut line_start = 0;
+    let mut line_number = 0;
+    while line_start < bytes.len() {
+        line_number += 1;
+        let line_end = bytes[line_start..].iter().position(|byte| *byte == b'\n').map(|offset| line_start + offset);
+        let line = String::from_utf8_lossy(&bytes[line_start..line_end.unwrap_or(bytes.len())]).to_string();
+        match (serde_json::from_str::<JournalEntry>(&line), line_end) {
+            (Ok(entry), Some(line_end)) => {
+                entries.push(entry);
+                line_start = line_end + 1;
+            },
+            (_, None) => {
+                // Only a line that was still being written can be missing its newline. Even if
+                // what's there happens to parse, the entry may not have been finished.
+                let file = OpenOptions::new().write(true).open(path)?;
+                file.set_len(line_start as u64)?;
+                file.sync_all()?;
+                dropped_line = Some(line);
+                break;
+            },
+            (Err(e), Some(_)) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("\"{}\" line {}: {}", path.display(), line_number, e)));
+            },
+        }
+    }
+
+    let last_snapshot_index = entries.iter().rposition(|entry| matches!(entry, JournalEntry::Snapshot { .. })).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("\"{}\" has no snapshot.", path.display())))?;
+    let mut entries = entries.split_off(last_snapshot_index).into_iter();
+    let mut record = match entries.next() {
+        Some(JournalEntry::Snapshot { mut record, undone_events }) => {
+            record.undone_events = undone_events;
+            record
+        },
+        _ => unreachable!(),
+    };
+    let mut entry_count = 0;
+    for entry in entries {
+        match entry {
+            JournalEntry::Event(event) => {
+                record.events.push(event);
+                record.undone_events.clear();
+            },
+            JournalEntry::Undo => {
+                record.move_last_group_to_undone();
+            },
+            JournalEntry::Redo => {
+                record.move_last_undone_group_back();
+            },
+            JournalEntry::Snapshot { .. } => unreachable!(),
+        }
+        entry_count += 1;
+    }
+    Ok(Recovery {
+        record,
+        entry_count,
+        dropped_line,
+    })
+}
+
+impl From<io::Error> for JournalError {
+    fn from(e: io::Error) -> Self {
+        JournalError::Io(e)
+    }
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::Lookup(e) => write!(f, "{}", e),
+            JournalError::Io(e) => write!(f, "The journal couldn't be written: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::data_source::DataSource;
+    use super::super::game_record::GameEventType;
+
+    #[test]
+    fn truncated_last_line_is_dropped_on_recovery() {
+        let path = std::env::temp_dir().join(format!("botw_journal_test_{}.{}", std::process::id(), JOURNAL_FILE_EXTENSION));
+        let mut model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        let mut record = GameRecord::new("Test");
+        let mut journal = Journal::create(&path, &record).unwrap();
+        journal.add_event(&mut record, &mut model, GameEvent::new(10, GameEventType::KorokSeed, "", Some(1))).unwrap();
+        journal.add_event(&mut record, &mut model, GameEvent::new(20, GameEventType::CompleteShrine, "Mezza Lo Shrine", None)).unwrap();
+        journal.add_event(&mut record, &mut model, GameEvent::new(30, GameEventType::KorokSeed, "", Some(2))).unwrap();
+        journal.undo(&mut record, &mut model).unwrap();
+        let complete_length = fs::metadata(&path).unwrap().len();
+
+        // A crash partway through writing the next entry leaves part of a line at the end.
+        let partial_line = &entry_to_line(&JournalEntry::Event(GameEvent::new(40, GameEventType::KorokSeed, "", Some(3)))).unwrap()[..20];
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(partial_line.as_bytes()).unwrap();
+
+        let (_, recovery) = Journal::open(&path).unwrap();
+        assert_eq!(recovery.dropped_line.as_deref(), Some(partial_line));
+        assert_eq!(record.events.len(), recovery.record.events.len());
+        assert_eq!(1, recovery.record.undone_events.len());
+        assert_eq!(complete_length, fs::metadata(&path).unwrap().len());
+
+        // Once the partial line has been cut off, the journal reads back cleanly.
+        assert!(recover(&path).unwrap().dropped_line.is_none());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,132 @@
+use std::fmt;
+
+use super::alias::AliasList;
+use super::model::{ItemType, LocationType};
+
+const MAX_SUGGESTIONS: usize = 3;
+
+// Why a name couldn't be turned into an entry in the model. These come from the try_get_*()
+// functions on Model so that a mistyped command or a stale game record can be reported instead of
+// crashing the tracker.
+#[derive(Clone, Debug)]
+pub enum LookupError {
+    Unknown {
+        list: AliasList,
+        name: String,
+        // Close matches for "did you mean", best first.
+        suggestions: Vec<String>,
+    },
+    WrongLocationType {
+        name: String,
+        expected: LocationType,
+        actual: LocationType,
+    },
+    WrongItemType {
+        name: String,
+        expected: ItemType,
+        actual: ItemType,
+    },
+    Ambiguous {
+        list: AliasList,
+        alias: String,
+        names: Vec<String>,
+    },
+    // A counter or setting event without the number to set it to, as from a hand-edited record.
+    MissingNumber {
+        event_type: String,
+    },
+    // An event being undone that doesn't remember the value it replaced.
+    NotApplied {
+        event: String,
+    },
+}
+
+impl LookupError {
+    pub fn new_unknown<'a>(list: AliasList, name: &str, candidates: impl Iterator<Item = &'a str>) -> Self {
+        LookupError::Unknown {
+            list,
+            name: name.to_string(),
+            suggestions: suggest(name, candidates),
+        }
+    }
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LookupError::Unknown { list, name, suggestions } => {
+                write!(f, "Unknown {} \"{}\".", list.variant_to_string().to_lowercase(), name)?;
+                if !suggestions.is_empty() {
+                    write!(f, " Did you mean {}?", quote_names(suggestions, "or"))?;
+                }
+                Ok(())
+            },
+            LookupError::WrongLocationType { name, expected, actual } => {
+                write!(f, "\"{}\" is {}, not {}.", name, with_article(actual.variant_to_string()), with_article(expected.variant_to_string()))
+            },
+            LookupError::WrongItemType { name, expected, actual } => {
+                write!(f, "\"{}\" is {}, not {}.", name, with_article(actual.variant_to_string()), with_article(expected.variant_to_string()))
+            },
+            LookupError::Ambiguous { list, alias, names } => {
+                write!(f, "The {} alias \"{}\" could be {}.", list.variant_to_string().to_lowercase(), alias, quote_names(names, "or"))
+            },
+            LookupError::MissingNumber { event_type } => {
+                write!(f, "The {} event has no number.", event_type)
+            },
+            LookupError::NotApplied { event } => {
+                write!(f, "\"{}\" was never applied, so it can't be undone.", event)
+            },
+        }
+    }
+}
+
+fn with_article(word: &str) -> String {
+    let article = if word.starts_with(|c: char| "AEIOU".contains(c)) { "an" } else { "a" };
+    format!("{} {}", article, word)
+}
+
+fn quote_names(names: &[String], conjunction: &str) -> String {
+    let names = names.iter().map(|name| format!("\"{}\"", name)).collect::<Vec<_>>();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} {} {}", rest.join(", "), conjunction, last),
+        _ => names.join(""),
+    }
+}
+
+// The candidates that are closest to the name, either because they contain it or because they're
+// only a few typos away.
+pub fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let name = name.trim().to_lowercase();
+    if name.is_empty() {
+        return vec![];
+    }
+    let max_distance = (name.chars().count() / 3).max(2);
+    let mut matches = candidates
+        .filter_map(|candidate| {
+            let lowercase = candidate.to_lowercase();
+            if lowercase.contains(&name) {
+                Some((0, candidate))
+            } else {
+                let distance = edit_distance(&name, &lowercase);
+                if distance <= max_distance { Some((distance, candidate)) } else { None }
+            }
+        })
+        .collect::<Vec<_>>();
+    matches.sort();
+    matches.into_iter().take(MAX_SUGGESTIONS).map(|(_, candidate)| candidate.to_string()).collect()
+}
+
+// The Levenshtein distance, counting characters rather than bytes.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_row[j] + if a_char == *b_char { 0 } else { 1 };
+            row.push(substitution.min(previous_row[j + 1] + 1).min(row[j] + 1));
+        }
+        previous_row = row;
+    }
+    previous_row[b.len()]
+}
@@ -1,9 +1,24 @@
+pub mod alias;
+pub mod arena;
+pub mod catalog;
 pub mod command;
+pub mod completion;
+pub mod dashboard;
+pub mod data_source;
 pub mod game_record;
+pub mod hierarchy;
+pub mod journal;
+pub mod lookup;
 pub mod model;
 pub mod parse;
+pub mod prerequisite;
+pub mod query;
+pub mod reload;
+pub mod replay;
 pub mod runtime;
+pub mod serialize;
 pub mod shell;
 pub mod sim;
+pub mod validate;
 
 
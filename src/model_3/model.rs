@@ -3,8 +3,7 @@
 
 use strum;
 use strum_macros::EnumString;
-use strum_macros::ToString;
-use std::string::ToString;
+use strum_macros::Display;
 
 use std::borrow::Borrow;
 use std::collections::HashMap;
@@ -15,20 +14,37 @@ use itertools::{sorted, Itertools};
 use std::fmt::Display;
 use serde::export::Formatter;
 use serde::export::fmt::Error;
+use serde::{Deserialize, Serialize};
 use std::{cmp, fmt};
 
 use crate::*;
 use super::parse;
+use super::alias::{AliasIndex, AliasList};
+use super::arena::*;
+use super::catalog;
+use super::data_source::DataSource;
 use super::game_record::GameEvent;
 use super::game_record::NULL_TIME;
+use super::lookup::LookupError;
+use super::query::Query;
 
 const QUEST_DEFEAT_ONE_DIVINE_BEAST: &str = "Defeat One Divine Beast";
 const QUEST_DEFEAT_TWO_DIVINE_BEASTS: &str = "Defeat Two Divine Beasts";
 const QUEST_DEFEAT_THREE_DIVINE_BEASTS: &str = "Defeat Three Divine Beasts";
 const QUEST_DEFEAT_FOUR_DIVINE_BEASTS: &str = "Defeat All Four Divine Beasts";
 const QUEST_COMPLETE_ALL_SHRINES: &str = "Complete All Shrines";
-const SHRINE_COUNT: u32 = 120;
-// const LOCATION_COUNT: u32 = 120;
+// Progress milestones that can be named as a requirement in the Items file like a quest.
+pub const MILESTONE_QUESTS: [&str; 5] = [QUEST_DEFEAT_ONE_DIVINE_BEAST, QUEST_DEFEAT_TWO_DIVINE_BEASTS,
+    QUEST_DEFEAT_THREE_DIVINE_BEASTS, QUEST_DEFEAT_FOUR_DIVINE_BEASTS, QUEST_COMPLETE_ALL_SHRINES];
+// A requirement such as "Reach Zora's Domain" is met by getting to the location.
+pub const PREFIX_REQUIREMENT_REACH: &str = "Reach ";
+pub const PREFIX_REQUIREMENT_CHEST: &str = "Chest at ";
+// What the game counts toward the completion percentage on the map screen.
+pub const SHRINE_COUNT: usize = 120;
+pub const KOROK_SEED_COUNT: usize = 900;
+pub const MAP_LOCATION_COUNT: usize = 226;
+pub const UPGRADE_LEVEL_COUNT: usize = 4;
+pub const ITEM_MON: &str = "Mon";
 
 pub fn main() {
     println!("\nBotW::model start\n");
@@ -38,13 +54,20 @@ pub fn main() {
 
 fn try_load() {
     let start = std::time::Instant::now();
-    let model = Model::new();
+    let model = match Model::new() {
+        Ok(model) => model,
+        Err(errors) => {
+            parse::print_errors(&errors);
+            return;
+        }
+    };
     util::date_time::print_elapsed_from_start(true, "new", "", start);
 
     model.report_characters();
     model.report_location_types();
     model.report_shrines();
     model.report_quest_types();
+    model.report_item_types();
 
     // model.try_load();
     //bg!(&model);
@@ -61,9 +84,42 @@ pub struct Model {
     pub chests: usize,
     pub deaths: usize,
     pub blood_moons: usize,
-    pub characters: BTreeMap<String, Character>,
-    pub locations: BTreeMap<String, Location>,
-    pub quests: BTreeMap<String, Quest>,
+    pub characters: Arena<CharacterId, Character>,
+    pub locations: Arena<LocationId, Location>,
+    pub quests: Arena<QuestId, Quest>,
+    pub items: Arena<ItemId, Item>,
+    pub recipes: BTreeMap<String, Recipe>,
+    pub recipes_by_ingredient: BTreeMap<String, Vec<String>>,
+    pub armor_sets: BTreeMap<String, ArmorSet>,
+    pub content_options: ContentOptions,
+    pub aliases: AliasIndex,
+    // How the datamined map file lined up with the locations when the text catalog was loaded.
+    // The structured catalog already has the map data joined in, so it's None for a model loaded
+    // from that.
+    pub map_location_match: Option<parse::MapLocationMatch>,
+    // Every entry in the datamined map file in file order, including the ones that aren't joined
+    // to a location.
+    pub map_entries: Vec<parse::MapLocationEntry>,
+}
+
+// Where a piece of content comes from. Anything not marked in the catalog files is Base.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum ContentPack {
+    #[default]
+    Base,
+    Dlc1,
+    Dlc2,
+    FreeDlc,
+    Amiibo,
+}
+
+// Which content packs to load into the model. Base game content is always loaded.
+#[derive(Clone, Debug)]
+pub struct ContentOptions {
+    pub dlc_1: bool,
+    pub dlc_2: bool,
+    pub free_dlc: bool,
+    pub amiibo: bool,
 }
 
 #[derive(Debug)]
@@ -73,30 +129,44 @@ pub struct Character {
     pub main: bool,
     pub champion: bool,
     pub merchant: bool,
+    // Whether the character is alive at the start of the game. See is_alive().
     pub alive: bool,
     pub mentioned_time: usize,
     pub met_time: usize,
     pub met_in_flashback_time: usize,
+    pub died_time: usize,
+    pub content_pack: ContentPack,
 }
 
 #[derive(Debug)]
 pub struct Location {
     pub name: String,
     pub typ: LocationType,
-    pub parent_location: Option<String>,
+    pub parent_location: Option<LocationId>,
     pub dog_treasure: Option<String>,
     pub discovered_time: usize,
     pub dog_treasure_found_time: usize,
     pub challenge: Option<String>,
-    pub quest: Option<String>,
+    pub quest: Option<QuestId>,
     pub started_time: usize,
     pub completed_time: usize,
     pub flame_lit_time: usize,
-    pub child_locations: Vec<String>,
+    pub activated_time: usize,
+    pub child_locations: Vec<LocationId>,
+    pub internal_name: Option<String>,
+    pub map_point: Option<MapPoint>,
+    pub content_pack: ContentPack,
+}
+
+// Position on the in-game map, in the coordinates used by the datamined map file.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MapPoint {
+    pub x: f64,
+    pub y: f64,
 }
 
 // #[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
-#[derive(Debug)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum LocationType {
     Region,
     Area,
@@ -108,20 +178,31 @@ pub enum LocationType {
     Normal,
 }
 
-/*
+#[derive(Debug)]
 pub struct Item {
     pub name: String,
     pub typ: ItemType,
-    pub quantity: usize,
-    pub needed: usize,
+    pub sequence: usize,
     pub effect: Option<Effect>,
-    pub upgrade_level: usize,
-    pub prices: Vec<Price>,
+    // The description as written in the Item Effects section, which the effect comes from.
+    pub effect_description: Option<String>,
+    // Some with an empty price for an item that's listed in the Purchase and Upgrade section
+    // without one.
+    pub purchase_price: Option<Price>,
+    // Set from the purchase price by Model::link_acquisition_sources().
+    pub acquisition_source: Option<AcquisitionSource>,
+    pub upgrade_prices: Vec<Price>,
     pub is_monster_part: bool,
     pub mon_sell_price: Option<usize>,
+    pub content_pack: ContentPack,
+    pub added_to_compendium_time: usize,
+    pub identified_time: usize,
+    // How many times a piece of armor has been upgraded, up to max_upgrade_level().
+    pub armor_level: usize,
+    pub count: usize,
 }
 
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
 pub enum ItemType {
     Money,
     Weapon,
@@ -134,7 +215,99 @@ pub enum ItemType {
     KeyItem,
 }
 
-*/
+// A purchase or upgrade price such as "3 Silent Princess, 2 Shard of Dinraal's Horn" or
+// "399 Mon, Defeat Two Divine Beasts".
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Price {
+    pub components: Vec<PriceComponent>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum PriceComponent {
+    Item {
+        name: String,
+        quantity: usize,
+    },
+    Requirement {
+        name: String,
+    },
+}
+
+#[derive(Debug)]
+pub struct Recipe {
+    pub name: String,
+    pub typ: RecipeType,
+    pub level: Option<usize>,
+    pub ingredients: Vec<Ingredient>,
+    // How roasted and frozen foods are made from the ingredients, such as "set on fire.".
+    pub method: Option<String>,
+    pub content_pack: ContentPack,
+}
+
+// One slot in a recipe, which can be filled by any of the alternatives such as
+// "Razorclaw Crab/Ironshell Crab/Bright-Eyed Crab".
+#[derive(Clone, Debug)]
+pub struct Ingredient {
+    pub alternatives: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum RecipeType {
+    Dish,
+    Elixir,
+    Roasted,
+    Frozen,
+}
+
+// Matching head, body, and legs pieces. The set bonus applies once all three pieces have been
+// upgraded twice.
+#[derive(Debug)]
+pub struct ArmorSet {
+    pub name: String,
+    pub set_bonus: Option<String>,
+    pub head: Option<ItemId>,
+    pub body: Option<ItemId>,
+    pub legs: Option<ItemId>,
+    // The places that sell the set, from the "shop:" lines.
+    pub shop_locations: Vec<LocationId>,
+    pub content_pack: ContentPack,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum ArmorSlot {
+    Head,
+    Body,
+    Legs,
+}
+
+// How to get an item, worked out from its purchase price in the Items file when the catalog is
+// loaded.
+#[derive(Clone, Debug)]
+pub enum AcquisitionSource {
+    // The price has only the Rupees, Mon, and materials. Requirements in the same price, as in
+    // "299 Mon, Defeat Two Divine Beasts", have to be met before the item can be bought. The
+    // locations are the shops for the item's armor set, if it has any.
+    Shop {
+        price: Price,
+        locations: Vec<LocationId>,
+        requirements: Vec<AcquisitionSource>,
+    },
+    Quest {
+        quest: QuestId,
+    },
+    Shrine {
+        shrine: LocationId,
+    },
+    Chest {
+        location: LocationId,
+    },
+    Reach {
+        location: LocationId,
+    },
+    Milestone {
+        name: &'static str,
+    },
+}
 
 #[derive(Debug)]
 pub struct Quest {
@@ -143,10 +316,16 @@ pub struct Quest {
     pub started_time: usize,
     pub completed_time: usize,
     pub notes: Option<String>,
-    pub shrine: Option<String>,
+    pub shrine: Option<LocationId>,
+    pub content_pack: ContentPack,
+    // For side quests, who gives the quest, where it starts, and which region it's in. The region
+    // comes from the start location if there is one.
+    pub giver: Option<CharacterId>,
+    pub start_location: Option<LocationId>,
+    pub region: Option<LocationId>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 // #[derive(EnumString)]
 pub enum QuestType {
     Main,
@@ -154,7 +333,7 @@ pub enum QuestType {
     Shrine,
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug, Deserialize, Serialize)]
 pub enum Effect {
     RestoreHearts,
     TemporaryHearts,
@@ -177,7 +356,7 @@ pub enum Effect {
     Stealth,
 }
 
-#[derive(Clone, Debug, EnumString, Eq, Ord, PartialEq, PartialOrd, ToString)]
+#[derive(Clone, Debug, Deserialize, Display, EnumString, Eq, Ord, PartialEq, PartialOrd, Serialize)]
 pub enum Race {
     Amiibo,
     GreatFairy,
@@ -197,8 +376,63 @@ pub enum Race {
 }
 
 impl Model {
-    pub fn new() -> Self {
-        let mut model = Self {
+    pub fn new() -> Result<Self, Vec<parse::ParseError>> {
+        Self::load(&DataSource::new_default())
+    }
+
+    pub fn load(source: &DataSource) -> Result<Self, Vec<parse::ParseError>> {
+        Self::load_with_options(source, &ContentOptions::default())
+    }
+
+    // Loads the format named by the BOTW_CATALOG_FORMAT environment variable, or if that isn't set
+    // the structured catalog when the source has an up-to-date one, otherwise the text files.
+    pub fn load_with_options(source: &DataSource, content_options: &ContentOptions) -> Result<Self, Vec<parse::ParseError>> {
+        let format = catalog::CatalogFormat::from_env().map_err(|error| vec![error])?;
+        Self::load_format(source, content_options, format)
+    }
+
+    pub fn load_format(source: &DataSource, content_options: &ContentOptions, format: Option<catalog::CatalogFormat>) -> Result<Self, Vec<parse::ParseError>> {
+        let format = match format {
+            Some(format) => format,
+            None => catalog::choose_format(source).map_err(|error| vec![error])?,
+        };
+        match format {
+            catalog::CatalogFormat::Text => Self::load_text(source, content_options),
+            catalog::CatalogFormat::Structured => catalog::load_model(source, content_options),
+        }
+    }
+
+    pub fn load_text(source: &DataSource, content_options: &ContentOptions) -> Result<Self, Vec<parse::ParseError>> {
+        let mut model = Self::new_empty(content_options);
+        // Keep going after a file fails so that all of the errors are reported at once.
+        let mut errors = vec![];
+        for result in [
+            parse::load_characters(&mut model, source),
+            parse::load_locations(&mut model, source),
+            parse::load_quests(&mut model, source),
+            parse::load_items(&mut model, source),
+            parse::load_aliases(&mut model, source),
+            parse::load_map_locations(&mut model, source).map(|map_match| model.map_location_match = Some(map_match)),
+            model.link_acquisition_sources(parse::FILE_NAME_ITEMS),
+        ] {
+            if let Err(mut file_errors) = result {
+                errors.append(&mut file_errors);
+            }
+        }
+        // Shrine::load_shrines(&mut model);
+
+        //bg!(&model);
+
+        if errors.is_empty() {
+            Ok(model)
+        } else {
+            Err(errors)
+        }
+    }
+
+    // A model with the starting stats and nothing in any of the lists.
+    pub fn new_empty(content_options: &ContentOptions) -> Self {
+        Self {
             hearts: 3,
             stamina: 5,
             weapon_slots: 8,
@@ -211,112 +445,606 @@ impl Model {
             characters: Default::default(),
             locations: Default::default(),
             quests: Default::default(),
-        };
-        parse::load_characters(&mut model);
-        parse::load_locations(&mut model);
-        parse::load_quests(&mut model);
-        // parse::load_items(&mut model);
-
-        // Shrine::load_shrines(&mut model);
-
-        //bg!(&model);
-
-        model
+            items: Default::default(),
+            recipes: Default::default(),
+            recipes_by_ingredient: Default::default(),
+            armor_sets: Default::default(),
+            content_options: content_options.clone(),
+            aliases: Default::default(),
+            map_location_match: None,
+            map_entries: vec![],
+        }
     }
 
-    pub fn add_character(&mut self, character: Character) {
+    pub fn add_character(&mut self, character: Character) -> CharacterId {
         let key = character.name.to_lowercase().to_string();
         assert!(key.trim().len() == key.len(), "character name \"{}\" is not trimmed.", &key);
         assert!(!self.characters.contains_key(&key));
-        self.characters.insert(key, character);
+        self.characters.insert(key, character)
     }
 
-    pub fn add_location(&mut self, location: Location) {
+    pub fn add_location(&mut self, location: Location) -> LocationId {
         let key = location.name.to_lowercase().to_string();
         assert!(key.trim().len() == key.len(), "location name \"{}\" is not trimmed.", &key);
         assert!(!self.locations.contains_key(&key), "Location {} already exists.", key);
-        self.locations.insert(key, location);
+        let parent_id = location.parent_location;
+        let id = self.locations.insert(key, location);
+        if let Some(parent_id) = parent_id {
+            self.locations.by_id_mut(parent_id).add_child_location(id);
+        }
+        id
     }
 
-    pub fn add_quest(&mut self, quest: Quest) {
+    pub fn add_quest(&mut self, quest: Quest) -> QuestId {
         let key = quest.name.to_lowercase().to_string();
         assert!(key.trim().len() == key.len(), "quest name \"{}\" is not trimmed.", &key);
         assert!(!self.quests.contains_key(&key));
-        self.quests.insert(key, quest);
+        self.quests.insert(key, quest)
     }
 
-    pub fn get_character<'a>(&'a self, name: &str) -> &'a Character {
-        let get = self.characters.get(&name.to_lowercase());
-        match get {
-            Some(character) => character,
-            None => panic!("Unknown character = \"{}\"", name),
+    pub fn add_item(&mut self, item: Item) -> ItemId {
+        let key = item.name.to_lowercase().to_string();
+        assert!(key.trim().len() == key.len(), "item name \"{}\" is not trimmed.", &key);
+        assert!(!self.items.contains_key(&key), "Item {} already exists.", key);
+        self.items.insert(key, item)
+    }
+
+    pub fn add_recipe(&mut self, recipe: Recipe) {
+        let key = recipe.name.to_lowercase().to_string();
+        assert!(key.trim().len() == key.len(), "recipe name \"{}\" is not trimmed.", &key);
+        assert!(!self.recipes.contains_key(&key), "Recipe {} already exists.", key);
+        for alternative in recipe.ingredients.iter().flat_map(|ingredient| ingredient.alternatives.iter()) {
+            let recipe_names = self.recipes_by_ingredient.entry(alternative.to_lowercase()).or_default();
+            if !recipe_names.contains(&recipe.name) {
+                recipe_names.push(recipe.name.clone());
+            }
+        }
+        self.recipes.insert(key, recipe);
+    }
+
+    // The key of the entry in the map with this name or alias, for lists like the recipes that
+    // aren't kept in an arena.
+    fn try_resolve_key<T>(&self, map: &BTreeMap<String, T>, entry_name: fn(&T) -> &str, alias_list: AliasList, name: &str) -> Result<String, LookupError> {
+        let key = name.trim().to_lowercase();
+        if map.contains_key(&key) {
+            return Ok(key);
+        }
+        let keys = self.aliases.get(alias_list, &key).iter().filter(|key| map.contains_key(*key)).collect::<Vec<_>>();
+        match keys.as_slice() {
+            [key] => Ok(key.to_string()),
+            [] => Err(LookupError::new_unknown(alias_list, name.trim(), map.values().map(entry_name))),
+            _ => Err(LookupError::Ambiguous {
+                list: alias_list,
+                alias: name.trim().to_string(),
+                names: keys.iter().map(|key| entry_name(&map[*key]).to_string()).collect(),
+            }),
+        }
+    }
+
+    // The IDs of the entries in the arena with this name or alias. There's more than one only for
+    // an ambiguous alias.
+    fn resolve_ids<I: EntityId, T>(&self, arena: &Arena<I, T>, alias_list: AliasList, name: &str) -> Vec<I> {
+        let key = name.trim().to_lowercase();
+        if let Some(id) = arena.id(&key) {
+            return vec![id];
+        }
+        // An alias may also point to entries from content packs that weren't loaded.
+        self.aliases.get(alias_list, &key).iter().filter_map(|key| arena.id(key)).collect()
+    }
+
+    // The ID of the entry in the arena with this name or alias, or an error that says why the name
+    // didn't resolve.
+    fn try_resolve_id<I: EntityId, T>(&self, arena: &Arena<I, T>, entry_name: fn(&T) -> &str, alias_list: AliasList, name: &str) -> Result<I, LookupError> {
+        let ids = self.resolve_ids(arena, alias_list, name);
+        match ids.as_slice() {
+            [id] => Ok(*id),
+            [] => Err(LookupError::new_unknown(alias_list, name.trim(), arena.values().map(entry_name))),
+            _ => Err(LookupError::Ambiguous {
+                list: alias_list,
+                alias: name.trim().to_string(),
+                names: ids.iter().map(|id| entry_name(arena.by_id(*id)).to_string()).collect(),
+            }),
         }
     }
 
+    // The same as try_resolve_id() but without building the suggestions for an unknown name,
+    // which means comparing the name against every entry.
+    fn resolve_id<I: EntityId, T>(&self, arena: &Arena<I, T>, alias_list: AliasList, name: &str) -> Option<I> {
+        match self.resolve_ids(arena, alias_list, name).as_slice() {
+            [id] => Some(*id),
+            _ => None,
+        }
+    }
+
+    // The ID of the entry with this name or alias. Anything that holds on to a reference to
+    // another entry keeps the ID rather than the name.
+    pub fn try_character_id(&self, name: &str) -> Result<CharacterId, LookupError> {
+        self.try_resolve_id(&self.characters, |character| &character.name, AliasList::Character, name)
+    }
+
+    pub fn try_location_id(&self, name: &str) -> Result<LocationId, LookupError> {
+        self.try_resolve_id(&self.locations, |location| &location.name, AliasList::Location, name)
+    }
+
+    pub fn try_quest_id(&self, name: &str) -> Result<QuestId, LookupError> {
+        self.try_resolve_id(&self.quests, |quest| &quest.name, AliasList::Quest, name)
+    }
+
+    pub fn try_item_id(&self, name: &str) -> Result<ItemId, LookupError> {
+        self.try_resolve_id(&self.items, |item| &item.name, AliasList::Item, name)
+    }
+
+    pub fn character_id(&self, name: &str) -> Option<CharacterId> {
+        self.resolve_id(&self.characters, AliasList::Character, name)
+    }
+
+    pub fn location_id(&self, name: &str) -> Option<LocationId> {
+        self.resolve_id(&self.locations, AliasList::Location, name)
+    }
+
+    pub fn quest_id(&self, name: &str) -> Option<QuestId> {
+        self.resolve_id(&self.quests, AliasList::Quest, name)
+    }
+
+    pub fn item_id(&self, name: &str) -> Option<ItemId> {
+        self.resolve_id(&self.items, AliasList::Item, name)
+    }
+
+    pub fn character_name(&self, id: CharacterId) -> &str {
+        &self.characters.by_id(id).name
+    }
+
+    pub fn location_name(&self, id: LocationId) -> &str {
+        &self.locations.by_id(id).name
+    }
+
+    pub fn quest_name(&self, id: QuestId) -> &str {
+        &self.quests.by_id(id).name
+    }
+
+    pub fn item_name(&self, id: ItemId) -> &str {
+        &self.items.by_id(id).name
+    }
+
+    // Whether the name or alias is in the list, for checking before one of the get_*() functions,
+    // which panic on unknown names.
+    pub fn contains_name(&self, alias_list: AliasList, name: &str) -> bool {
+        match alias_list {
+            AliasList::Character => self.character_id(name).is_some(),
+            AliasList::Location => self.location_id(name).is_some(),
+            AliasList::Quest => self.quest_id(name).is_some(),
+            AliasList::Item => self.item_id(name).is_some(),
+        }
+    }
+
+    // The try_get_*() functions return an error for an unknown or ambiguous name or a location of
+    // the wrong type. The get_*() functions panic instead, for names that are known to be good
+    // such as those taken from the model itself.
+    pub fn try_get_character<'a>(&'a self, name: &str) -> Result<&'a Character, LookupError> {
+        self.try_character_id(name).map(|id| self.characters.by_id(id))
+    }
+
+    pub fn try_get_character_mut<'a>(&'a mut self, name: &str) -> Result<&'a mut Character, LookupError> {
+        let id = self.try_character_id(name)?;
+        Ok(self.characters.by_id_mut(id))
+    }
+
+    pub fn get_character<'a>(&'a self, name: &str) -> &'a Character {
+        self.try_get_character(name).unwrap_or_else(|e| panic!("{}", e))
+    }
+
     pub fn get_character_mut<'a>(&'a mut self, name: &str) -> &'a mut Character {
-        let get = self.characters.get_mut(&name.to_lowercase());
-        match get {
-            Some(character) => character,
-            None => panic!("Unknown character = \"{}\"", name),
+        match self.try_get_character_mut(name) {
+            Ok(character) => character,
+            Err(e) => panic!("{}", e),
         }
     }
 
+    pub fn try_get_location<'a>(&'a self, name: &str) -> Result<&'a Location, LookupError> {
+        self.try_location_id(name).map(|id| self.locations.by_id(id))
+    }
+
+    pub fn try_get_location_mut<'a>(&'a mut self, name: &str) -> Result<&'a mut Location, LookupError> {
+        let id = self.try_location_id(name)?;
+        Ok(self.locations.by_id_mut(id))
+    }
+
     pub fn get_location<'a>(&'a self, name: &str) -> &'a Location {
-        let get = self.locations.get(&name.to_lowercase());
-        match get {
-            Some(location) => location,
-            None => panic!("Unknown location = \"{}\"", name),
-        }
+        self.try_get_location(name).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn get_location_mut<'a>(&'a mut self, name: &str) -> &'a mut Location {
-        let get = self.locations.get_mut(&name.to_lowercase());
-        match get {
-            Some(location) => location,
-            None => panic!("Unknown location = \"{}\"", name),
+        match self.try_get_location_mut(name) {
+            Ok(location) => location,
+            Err(e) => panic!("{}", e),
         }
     }
 
+    pub fn try_get_parent_location<'a>(&'a self, name: &str) -> Result<Option<&'a Location>, LookupError> {
+        Ok(self.try_get_location(name)?.parent_location.map(|parent_id| self.locations.by_id(parent_id)))
+    }
+
     pub fn get_parent_location<'a>(&'a self, name: &str) -> Option<&'a Location> {
-        self.get_location(name).parent_location.as_ref().map(|parent_name| self.get_location(&parent_name))
+        self.try_get_parent_location(name).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_get_shrine<'a>(&'a self, name: &str) -> Result<&'a Location, LookupError> {
+        let location = self.try_get_location(name)?;
+        check_location_type(location, LocationType::Shrine)?;
+        Ok(location)
+    }
+
+    pub fn try_get_shrine_mut<'a>(&'a mut self, name: &str) -> Result<&'a mut Location, LookupError> {
+        let location = self.try_get_location_mut(name)?;
+        check_location_type(location, LocationType::Shrine)?;
+        Ok(location)
+    }
+
+    pub fn try_get_tower<'a>(&'a self, name: &str) -> Result<&'a Location, LookupError> {
+        let location = self.try_get_location(name)?;
+        check_location_type(location, LocationType::Tower)?;
+        Ok(location)
+    }
+
+    pub fn try_get_tower_mut<'a>(&'a mut self, name: &str) -> Result<&'a mut Location, LookupError> {
+        let location = self.try_get_location_mut(name)?;
+        check_location_type(location, LocationType::Tower)?;
+        Ok(location)
     }
 
     pub fn get_shrine<'a>(&'a self, name: &str) -> &'a Location {
-        let location = self.get_location(name);
-        match location.typ {
-            LocationType::Shrine => location,
-            _ => panic!("Location \"{}\" is not a shrine.", name),
-        }
+        self.try_get_shrine(name).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn get_shrine_mut<'a>(&'a mut self, name: &str) -> &'a mut Location {
-        let location = self.get_location_mut(name);
-        match location.typ {
-            LocationType::Shrine => location,
-            _ => panic!("Location \"{}\" is not a shrine.", name),
+        match self.try_get_shrine_mut(name) {
+            Ok(location) => location,
+            Err(e) => panic!("{}", e),
         }
     }
 
     pub fn get_shrine_quest<'a>(&'a self, name: &str) -> Option<&'a Quest> {
-        self.get_shrine(name).quest.as_ref().map(|quest_name| self.get_quest(&quest_name))
+        self.get_shrine(name).quest.map(|quest_id| self.quests.by_id(quest_id))
     }
 
-    pub fn get_quest<'a>(&'a self, name: &str) -> &'a Quest {
-        let get = self.quests.get(&name.to_lowercase());
-        match get {
-            Some(quest) => quest,
-            None => panic!("Unknown quest = \"{}\"", name),
+    // The region that the location is in, or the location itself if it's a region.
+    pub fn try_get_containing_region<'a>(&'a self, name: &str) -> Result<Option<&'a Location>, LookupError> {
+        let id = self.try_location_id(name)?;
+        Ok(self.get_containing_region_id(id).map(|region_id| self.locations.by_id(region_id)))
+    }
+
+    pub fn get_containing_region_id(&self, id: LocationId) -> Option<LocationId> {
+        std::iter::once(id)
+            .chain(self.ancestors(id))
+            .find(|location_id| self.locations.by_id(*location_id).typ == LocationType::Region)
+    }
+
+    // Sets the quest's giver, start location, and region from their names. A quest with a start
+    // location is in the region that contains it, so a region that's also named has to be that one.
+    // The returned error is the expected shape of the names.
+    pub fn link_quest(&self, quest: &mut Quest, giver: Option<&str>, start_location: Option<&str>, region: Option<&str>) -> Result<(), String> {
+        if let Some(name) = giver.map(str::trim) {
+            quest.giver = Some(self.characters.id(&name.to_lowercase()).ok_or_else(|| format!("a known character as the giver \"{}\"", name))?);
         }
+        if let Some(name) = start_location.map(str::trim) {
+            quest.start_location = Some(self.locations.id(&name.to_lowercase()).ok_or_else(|| format!("a known location as the start location \"{}\"", name))?);
+        }
+        if let Some(name) = region.map(str::trim) {
+            quest.region = Some(self.locations.id(&name.to_lowercase()).ok_or_else(|| format!("a known region as the region \"{}\"", name))?);
+        }
+        if quest.region.is_none() {
+            quest.region = quest.start_location.and_then(|location_id| self.get_containing_region_id(location_id));
+        }
+        self.check_quest_links(quest)
+    }
+
+    // Whether the quest's giver, start location, and region are in the model and agree with each
+    // other. This is the one place these are checked, both when the catalog is loaded and when
+    // it's validated.
+    pub fn check_quest_links(&self, quest: &Quest) -> Result<(), String> {
+        if let Some(giver_id) = quest.giver {
+            self.characters.get_by_id(giver_id).ok_or("a known character as the giver")?;
+        }
+        if let Some(start_location_id) = quest.start_location {
+            self.locations.get_by_id(start_location_id).ok_or("a known location as the start location")?;
+        }
+        if let Some(region_id) = quest.region {
+            match self.locations.get_by_id(region_id) {
+                Some(location) if location.typ == LocationType::Region => {},
+                Some(location) => return Err(format!("a region rather than \"{}\" as the region", location.name)),
+                None => return Err("a known location as the region".to_string()),
+            }
+            if let Some(start_location_id) = quest.start_location {
+                if self.get_containing_region_id(start_location_id) != Some(region_id) {
+                    return Err(format!("a region that contains the start location \"{}\"", self.location_name(start_location_id)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Whether the location is the ancestor or somewhere under it.
+    pub fn location_is_within(&self, id: LocationId, ancestor_id: LocationId) -> bool {
+        id == ancestor_id || self.ancestors(id).any(|location_id| location_id == ancestor_id)
+    }
+
+    pub fn query_locations(&self) -> Query<'_, Location> {
+        Query::new(self)
+    }
+
+    pub fn query_quests(&self) -> Query<'_, Quest> {
+        Query::new(self)
+    }
+
+    pub fn query_characters(&self) -> Query<'_, Character> {
+        Query::new(self)
+    }
+
+    pub fn query_items(&self) -> Query<'_, Item> {
+        Query::new(self)
+    }
+
+    pub fn quests_in_region<'a>(&'a self, region_name: &str) -> Vec<&'a Quest> {
+        let region_id = self.location_id(region_name);
+        assert!(region_id.is_some(), "Unknown location = \"{}\"", region_name);
+        self.quests
+            .values()
+            .filter(|quest| quest.region == region_id)
+            .collect()
+    }
+
+    pub fn quests_given_by<'a>(&'a self, character_name: &str) -> Vec<&'a Quest> {
+        let character_id = self.character_id(character_name);
+        assert!(character_id.is_some(), "Unknown character = \"{}\"", character_name);
+        self.quests
+            .values()
+            .filter(|quest| quest.giver == character_id)
+            .collect()
+    }
+
+    pub fn try_get_quest<'a>(&'a self, name: &str) -> Result<&'a Quest, LookupError> {
+        self.try_quest_id(name).map(|id| self.quests.by_id(id))
+    }
+
+    pub fn try_get_quest_mut<'a>(&'a mut self, name: &str) -> Result<&'a mut Quest, LookupError> {
+        let id = self.try_quest_id(name)?;
+        Ok(self.quests.by_id_mut(id))
+    }
+
+    pub fn get_quest<'a>(&'a self, name: &str) -> &'a Quest {
+        self.try_get_quest(name).unwrap_or_else(|e| panic!("{}", e))
     }
 
     pub fn get_quest_mut<'a>(&'a mut self, name: &str) -> &'a mut Quest {
-        let get = self.quests.get_mut(&name.to_lowercase());
+        match self.try_get_quest_mut(name) {
+            Ok(quest) => quest,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_get_item<'a>(&'a self, name: &str) -> Result<&'a Item, LookupError> {
+        self.try_item_id(name).map(|id| self.items.by_id(id))
+    }
+
+    pub fn try_get_item_mut<'a>(&'a mut self, name: &str) -> Result<&'a mut Item, LookupError> {
+        let id = self.try_item_id(name)?;
+        Ok(self.items.by_id_mut(id))
+    }
+
+    pub fn try_get_armor<'a>(&'a self, name: &str) -> Result<&'a Item, LookupError> {
+        let item = self.try_get_item(name)?;
+        check_item_type(item, ItemType::Armor)?;
+        Ok(item)
+    }
+
+    pub fn try_get_armor_mut<'a>(&'a mut self, name: &str) -> Result<&'a mut Item, LookupError> {
+        let item = self.try_get_item_mut(name)?;
+        check_item_type(item, ItemType::Armor)?;
+        Ok(item)
+    }
+
+    pub fn get_item<'a>(&'a self, name: &str) -> &'a Item {
+        self.try_get_item(name).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn get_item_mut<'a>(&'a mut self, name: &str) -> &'a mut Item {
+        match self.try_get_item_mut(name) {
+            Ok(item) => item,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn add_armor_set(&mut self, armor_set: ArmorSet) {
+        let key = armor_set.name.to_lowercase().to_string();
+        assert!(key.trim().len() == key.len(), "armor set name \"{}\" is not trimmed.", &key);
+        assert!(!self.armor_sets.contains_key(&key), "Armor set {} already exists.", key);
+        self.armor_sets.insert(key, armor_set);
+    }
+
+    pub fn get_armor_set<'a>(&'a self, name: &str) -> &'a ArmorSet {
+        let get = self.armor_sets.get(&name.to_lowercase());
         match get {
-            Some(quest) => quest,
-            None => panic!("Unknown quest = \"{}\"", name),
+            Some(armor_set) => armor_set,
+            None => panic!("Unknown armor set = \"{}\"", name),
         }
     }
 
+    pub fn get_armor_set_for_item<'a>(&'a self, item_name: &str) -> Option<&'a ArmorSet> {
+        let item_id = self.item_id(item_name).unwrap_or_else(|| panic!("Unknown item = \"{}\"", item_name));
+        self.armor_sets.values().find(|armor_set| armor_set.slot_of(item_id).is_some())
+    }
+
+    // The milestone with this name, ignoring case, as it's written in MILESTONE_QUESTS.
+    pub fn milestone_name(name: &str) -> Option<&'static str> {
+        MILESTONE_QUESTS
+            .iter()
+            .find(|milestone| milestone.eq_ignore_ascii_case(name.trim()))
+            .copied()
+    }
+
+    // Whether a requirement in a price names a quest, location, or milestone that's known.
+    pub fn is_known_requirement(&self, name: &str) -> bool {
+        self.requirement_source(name).is_some()
+    }
+
+    // What a requirement in a price such as "Locked Mementos" or "Reach Zora's Domain" asks for.
+    // Names are looked up with their aliases. A location is a chest only when the requirement says
+    // "Chest at", and any other location that isn't a shrine just has to be reached.
+    pub fn requirement_source(&self, name: &str) -> Option<AcquisitionSource> {
+        let name = name.trim();
+        if let Some(location_name) = name.strip_prefix(PREFIX_REQUIREMENT_REACH) {
+            return self.location_id(location_name).map(|location| AcquisitionSource::Reach { location });
+        }
+        if let Some(location_name) = name.strip_prefix(PREFIX_REQUIREMENT_CHEST) {
+            return self.location_id(location_name).map(|location| AcquisitionSource::Chest { location });
+        }
+        if let Some(quest) = self.quest_id(name) {
+            return Some(AcquisitionSource::Quest { quest });
+        }
+        if let Some(location_id) = self.location_id(name) {
+            return Some(match self.locations.by_id(location_id).typ {
+                LocationType::Shrine => AcquisitionSource::Shrine { shrine: location_id },
+                _ => AcquisitionSource::Reach { location: location_id },
+            });
+        }
+        Self::milestone_name(name).map(|name| AcquisitionSource::Milestone { name })
+    }
+
+    // Where the item comes from, if its purchase price says. A price with Rupees, Mon, or
+    // materials means a shop, and the shop's locations come from the item's armor set. A price
+    // that's only a requirement names a quest, shrine, chest, location, or milestone. It's None if
+    // there are several requirements and nothing to pay. The returned error is the expected shape
+    // of a requirement that isn't known.
+    fn find_acquisition_source(&self, item: &Item) -> Result<Option<AcquisitionSource>, String> {
+        let price = match &item.purchase_price {
+            Some(price) => price,
+            None => return Ok(None),
+        };
+        let (cost, requirements): (Vec<PriceComponent>, Vec<PriceComponent>) = price.components
+            .iter()
+            .cloned()
+            .partition(|component| matches!(component, PriceComponent::Item { .. }));
+        let mut requirements = requirements
+            .iter()
+            .map(|component| self.requirement_source(component.name())
+                .ok_or_else(|| format!("a known quest, shrine, location, or milestone as the requirement \"{}\"", component.name())))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !cost.is_empty() {
+            let locations = self.get_armor_set_for_item(&item.name).map_or(vec![], |armor_set| armor_set.shop_locations.clone());
+            return Ok(Some(AcquisitionSource::Shop { price: Price { components: cost }, locations, requirements }));
+        }
+        Ok(if requirements.len() == 1 { requirements.pop() } else { None })
+    }
+
+    // Works out where each item comes from once everything that a price can name, including the
+    // aliases, has been loaded. A requirement that isn't a known quest, shrine, location, or
+    // milestone is an error.
+    pub fn link_acquisition_sources(&mut self, file_name: &str) -> Result<(), Vec<parse::ParseError>> {
+        let mut errors = vec![];
+        let mut sources = vec![];
+        for (item_id, item) in self.items.iter_ids() {
+            match self.find_acquisition_source(item) {
+                Ok(source) => sources.push((item_id, source)),
+                Err(error) => errors.push(parse::ParseError::new(file_name, None, &item.name, &error)),
+            }
+        }
+        for (item_id, source) in sources {
+            self.items.by_id_mut(item_id).acquisition_source = source;
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn acquisition_source<'a>(&'a self, item_name: &str) -> Option<&'a AcquisitionSource> {
+        self.get_item(item_name).acquisition_source.as_ref()
+    }
+
+    // The pieces of armor that aren't in the inventory, which maps item names to counts, in
+    // Hyrule Compendium order. Each comes with its armor set, if it's in one, and how to get it.
+    pub fn missing_armor<'a>(&'a self, inventory: &BTreeMap<String, usize>) -> Vec<(Option<&'a ArmorSet>, &'a Item, Option<&'a AcquisitionSource>)> {
+        let owned: Vec<ItemId> = inventory
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .filter_map(|(name, _)| self.item_id(name))
+            .collect();
+        let mut missing = self.items
+            .iter_ids()
+            .filter(|(item_id, item)| item.typ == ItemType::Armor && !owned.contains(item_id))
+            .map(|(item_id, item)| {
+                let armor_set = self.armor_sets.values().find(|armor_set| armor_set.slot_of(item_id).is_some());
+                (armor_set, item, item.acquisition_source.as_ref())
+            })
+            .collect::<Vec<_>>();
+        missing.sort_by_key(|(_, item, _)| item.sequence);
+        missing
+    }
+
+    pub fn items_in_order(&self) -> Vec<&Item> {
+        let mut v: Vec<&Item> = self.items.values().collect();
+        v.sort_by_key(|item| item.sequence);
+        v
+    }
+
+    pub fn try_get_recipe<'a>(&'a self, name: &str) -> Result<&'a Recipe, LookupError> {
+        self.try_resolve_key(&self.recipes, |recipe| &recipe.name, AliasList::Item, name).map(|key| &self.recipes[&key])
+    }
+
+    pub fn get_recipe<'a>(&'a self, name: &str) -> &'a Recipe {
+        self.try_get_recipe(name).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn recipes_with_ingredient<'a>(&'a self, item_name: &str) -> Vec<&'a Recipe> {
+        self.recipes_by_ingredient
+            .get(&item_name.to_lowercase())
+            .map_or(vec![], |recipe_names| recipe_names.iter().map(|recipe_name| self.get_recipe(recipe_name)).collect())
+    }
+
+    // Recipes whose ingredients can all be taken from the inventory, which maps item names to
+    // counts. Recipes with no listed ingredients such as elixirs are left out.
+    pub fn makeable_recipes<'a>(&'a self, inventory: &BTreeMap<String, usize>) -> Vec<&'a Recipe> {
+        let counts: BTreeMap<String, usize> = inventory.iter().map(|(name, count)| (name.to_lowercase(), *count)).collect();
+        self.recipes
+            .values()
+            .filter(|recipe| !recipe.ingredients.is_empty() && recipe.can_make(&counts))
+            .collect()
+    }
+
+    // The number of Mon that Kilton would pay for everything in the inventory, which maps item
+    // names or aliases to counts. Items he doesn't buy and unknown names are ignored.
+    pub fn mon_value(&self, inventory: &BTreeMap<String, usize>) -> usize {
+        inventory
+            .iter()
+            .map(|(name, count)| {
+                self.try_get_item(name).ok()
+                    .and_then(|item| item.mon_sell_price)
+                    .map_or(0, |mon_price| mon_price * count)
+            })
+            .sum()
+    }
+
+    // Whether the Mon on hand plus the Mon from selling the rest of the inventory covers the Mon
+    // part of the item's purchase price. Materials that the same price takes can't also be sold.
+    pub fn can_afford_in_mon(&self, item_name: &str, inventory: &BTreeMap<String, usize>) -> Result<bool, LookupError> {
+        let price = match &self.try_get_item(item_name)?.purchase_price {
+            Some(price) => price,
+            None => return Ok(true),
+        };
+        let mut mon_on_hand = 0;
+        let mut sellable = BTreeMap::new();
+        for (name, count) in inventory.iter() {
+            if name.eq_ignore_ascii_case(ITEM_MON) {
+                mon_on_hand += count;
+            } else {
+                let spent = self.try_get_item(name).map_or(0, |item| price.quantity_of(&item.name));
+                sellable.insert(name.clone(), count.saturating_sub(spent));
+            }
+        }
+        Ok(mon_on_hand + self.mon_value(&sellable) >= price.quantity_of(ITEM_MON))
+    }
+
     pub fn try_load(&mut self) {
         let start = std::time::Instant::now();
         // Item::load_inventory(self);
@@ -350,12 +1078,8 @@ impl Model {
 
     pub fn report_location_types(&self) {
         let mut grouper = util::group::Grouper::new("Location Types");
-        for type_name in self
-            .locations
-            .values()
-            .map(|location| location.typ.variant_to_string()) {
-
-            grouper.record_entry(&type_name);
+        for location in self.query_locations().run() {
+            grouper.record_entry(&location.typ.variant_to_string());
         }
         grouper.print_by_count(0, None);
     }
@@ -363,34 +1087,31 @@ impl Model {
     pub fn report_shrines(&self) {
         let mut grouper_challenge = util::group::Grouper::new("Shrine Challenges");
         let mut grouper_has_quest = util::group::Grouper::new("Shrine Has Quest");
-        for (challenge, has_quest) in self
-            .locations
-            .values()
-            .map(|location| {
-                let Location { typ, parent_location: _, dog_treasure: _, discovered_time: _, dog_treasure_found_time: _, challenge, quest, .. } = location;
-                match typ {
-                    LocationType::Shrine => Some((challenge.as_ref().unwrap(), quest.is_some())),
-                    _ => None,
-                }
-            })
-            .filter(|entry| entry.is_some())
-            .map(|entry| entry.unwrap()) {
-
-            grouper_challenge.record_entry(&challenge);
-            grouper_has_quest.record_entry(&has_quest);
+        for shrine in self.query_locations().typ(LocationType::Shrine).run() {
+            grouper_challenge.record_entry(shrine.challenge.as_ref().unwrap());
+            grouper_has_quest.record_entry(&shrine.quest.is_some());
         }
         grouper_challenge.print_by_count(0, None);
         grouper_has_quest.print_by_count(0, None);
     }
 
+    pub fn report_item_types(&self) {
+        let mut grouper_type = util::group::Grouper::new("Item Types");
+        let mut grouper_effect = util::group::Grouper::new("Item Effects");
+        for item in self.items.values() {
+            grouper_type.record_entry(&item.typ.variant_to_string());
+            if let Some(effect) = item.effect {
+                grouper_effect.record_entry(&effect.variant_to_string());
+            }
+        }
+        grouper_type.print_by_count(0, None);
+        grouper_effect.print_by_count(0, None);
+    }
+
     pub fn report_quest_types(&self) {
         let mut grouper = util::group::Grouper::new("Quest Types");
-        for type_name in self
-            .quests
-            .values()
-            .map(|quest| quest.typ.variant_to_string()) {
-
-            grouper.record_entry(&type_name);
+        for quest in self.query_quests().run() {
+            grouper.record_entry(&quest.typ.variant_to_string());
         }
         grouper.print_by_count(0, None);
     }
@@ -406,6 +1127,30 @@ impl Model {
     */
 }
 
+pub fn check_location_type(location: &Location, expected: LocationType) -> Result<(), LookupError> {
+    if location.typ == expected {
+        Ok(())
+    } else {
+        Err(LookupError::WrongLocationType {
+            name: location.name.clone(),
+            expected,
+            actual: location.typ.clone(),
+        })
+    }
+}
+
+pub fn check_item_type(item: &Item, expected: ItemType) -> Result<(), LookupError> {
+    if item.typ == expected {
+        Ok(())
+    } else {
+        Err(LookupError::WrongItemType {
+            name: item.name.clone(),
+            expected,
+            actual: item.typ,
+        })
+    }
+}
+
 impl Character {
     pub fn new(name: &str, race: &Race, main: bool, champion: bool, merchant: bool, alive: bool) -> Self {
         Self {
@@ -418,6 +1163,8 @@ impl Character {
             mentioned_time: NULL_TIME,
             met_time: NULL_TIME,
             met_in_flashback_time: NULL_TIME,
+            died_time: NULL_TIME,
+            content_pack: ContentPack::Base,
         }
     }
 
@@ -433,8 +1180,16 @@ impl Character {
         self.met_in_flashback_time != NULL_TIME
     }
 
+    pub fn has_died(&self) -> bool {
+        self.died_time != NULL_TIME
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.alive && !self.has_died()
+    }
+
     pub fn status_description(&self, _model: &Model) -> String {
-        match (self.alive, self.is_mentioned(), self.is_met_in_flashback(), self.is_met()) {
+        let description = match (self.alive, self.is_mentioned(), self.is_met_in_flashback(), self.is_met()) {
             (false, false, false, _) => "dead, not mentioned or met",
             (false, false, true, _) => "dead, met in flashback",
             (false, true, false, _) => "dead, mentioned but not met",
@@ -448,13 +1203,18 @@ impl Character {
             (true, true, true, false) => "mentioned and met in flashback",
             (true, true, true, true) => "mentioned and met",
             // (_, _, _, _) => &format!("[[Unexpected combination: {} {} {} {}]]", character.alive, character.is_mentioned(), character.is_met_in_flashback(), character.is_met()),
-        }.to_string()
+        };
+        if self.has_died() {
+            format!("{}; died", description)
+        } else {
+            description.to_string()
+        }
     }
 
 }
 
 impl Location {
-    pub fn new(name: &str, typ: LocationType, parent_location: Option<String>) -> Self {
+    pub fn new(name: &str, typ: LocationType, parent_location: Option<LocationId>) -> Self {
         Self {
             name: name.to_string(),
             discovered_time: NULL_TIME,
@@ -467,15 +1227,17 @@ impl Location {
             started_time: NULL_TIME,
             completed_time: NULL_TIME,
             flame_lit_time: NULL_TIME,
-            child_locations: vec![]
+            activated_time: NULL_TIME,
+            child_locations: vec![],
+            internal_name: None,
+            map_point: None,
+            content_pack: ContentPack::Base,
         }
     }
 
-    pub fn add_child_location(&mut self, name: &str) {
-        let name = name.to_string();
-        assert!(name.trim().len() == name.len(), "location name \"{}\" is not trimmed.", &name);
-        assert!(!self.child_locations.contains(&name), "Location {} already exists.", name);
-        self.child_locations.push(name);
+    pub fn add_child_location(&mut self, id: LocationId) {
+        assert!(!self.child_locations.contains(&id), "Location {:?} already exists.", id);
+        self.child_locations.push(id);
     }
 
     pub fn is_discovered(&self) -> bool {
@@ -520,6 +1282,10 @@ impl Location {
         self.flame_lit_time != NULL_TIME
     }
 
+    pub fn is_activated(&self) -> bool {
+        self.activated_time != NULL_TIME
+    }
+
     pub fn name_with_shrine_challenge(&self) -> String {
         let challenge = self.challenge.as_ref().map_or("".to_string(), |challenge| format!(" ({})", challenge));
         format!("{}{}", self.name, challenge)
@@ -539,12 +1305,12 @@ impl Location {
             _ => "".to_string(),
         };
 
-        let quest_note = match &self.quest {
-            Some(quest_name) => format!("; quest {} is {}", quest_name, model.get_quest(&quest_name).completion_label()),
+        let quest_note = match self.quest.map(|quest_id| model.quests.by_id(quest_id)) {
+            Some(quest) => format!("; quest {} is {}", quest.name, quest.completion_label()),
             None => "".to_string(),
         };
 
-        let flame_note = match self.typ {
+        let type_note = match self.typ {
             LocationType::TechLab => {
                 if self.is_flame_lit() {
                     "; flame is lit".to_string()
@@ -552,14 +1318,34 @@ impl Location {
                     "; flame is not yet lit".to_string()
                 }
             },
+            LocationType::Tower => {
+                if self.is_activated() {
+                    "; tower is activated".to_string()
+                } else {
+                    "; tower is not yet activated".to_string()
+                }
+            },
             _ => "".to_string(),
         };
 
-        format!("{}{}{}{}{}", discovered_note, dog_treasure_note, shrine_note, quest_note, flame_note)
+        format!("{}{}{}{}{}", discovered_note, dog_treasure_note, shrine_note, quest_note, type_note)
     }
 
 }
 
+impl MapPoint {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self {
+            x,
+            y,
+        }
+    }
+
+    pub fn distance_to(&self, other: &MapPoint) -> f64 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
+    }
+}
+
 impl LocationType {
     pub fn variant_to_string<'a>(&self) -> &'a str {
         match self {
@@ -576,7 +1362,7 @@ impl LocationType {
 }
 
 impl Quest {
-    fn new(name: &str, typ: QuestType, notes: Option<String>, shrine: Option<String>) -> Self {
+    fn new(name: &str, typ: QuestType, notes: Option<String>, shrine: Option<LocationId>) -> Self {
         Self {
             name: name.to_string(),
             typ,
@@ -584,6 +1370,10 @@ impl Quest {
             completed_time: NULL_TIME,
             notes,
             shrine,
+            content_pack: ContentPack::Base,
+            giver: None,
+            start_location: None,
+            region: None,
         }
     }
 
@@ -595,8 +1385,8 @@ impl Quest {
         Self::new(name, QuestType::Side, notes, None)
     }
 
-    pub fn new_shrine(name: &str, shrine: &str) -> Self {
-        Self::new(name, QuestType::Shrine, None, Some(shrine.to_string()))
+    pub fn new_shrine(name: &str, shrine: LocationId) -> Self {
+        Self::new(name, QuestType::Shrine, None, Some(shrine))
     }
 
     pub fn is_started(&self) -> bool {
@@ -623,11 +1413,14 @@ impl Quest {
 
         let type_note = match self.typ {
             QuestType::Main => "main quest".to_string(),
-            QuestType::Side => "side quest".to_string(),
+            QuestType::Side => {
+                let giver_note = self.giver.map_or("".to_string(), |giver| format!(" from {}", model.character_name(giver)));
+                let place_note = self.start_location.or(self.region).map_or("".to_string(), |place| format!(" in {}", model.location_name(place)));
+                format!("side quest{}{}", giver_note, place_note)
+            },
             QuestType::Shrine => {
-                let shrine_name = self.shrine.as_ref().unwrap();
-                let shrine_completion = model.get_shrine(&shrine_name).completion_label();
-                format!("shrine quest for {} ({})", shrine_name, shrine_completion)
+                let shrine = model.locations.by_id(self.shrine.unwrap());
+                format!("shrine quest for {} ({})", shrine.name, shrine.completion_label())
             },
         };
 
@@ -636,6 +1429,397 @@ impl Quest {
 
 }
 
+impl Item {
+    pub fn new(name: &str, typ: ItemType, sequence: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            typ,
+            sequence,
+            effect: None,
+            effect_description: None,
+            purchase_price: None,
+            acquisition_source: None,
+            upgrade_prices: vec![],
+            is_monster_part: false,
+            mon_sell_price: None,
+            content_pack: ContentPack::Base,
+            added_to_compendium_time: NULL_TIME,
+            identified_time: NULL_TIME,
+            armor_level: 0,
+            count: 0,
+        }
+    }
+
+    pub fn has_purchase_price(&self) -> bool {
+        matches!(&self.purchase_price, Some(price) if !price.components.is_empty())
+    }
+
+    pub fn max_upgrade_level(&self) -> usize {
+        self.upgrade_prices.len()
+    }
+
+    pub fn is_in_compendium(&self) -> bool {
+        self.added_to_compendium_time != NULL_TIME
+    }
+
+    pub fn is_identified(&self) -> bool {
+        self.identified_time != NULL_TIME
+    }
+
+    pub fn can_upgrade(&self) -> bool {
+        self.typ == ItemType::Armor && self.armor_level < self.max_upgrade_level()
+    }
+
+    pub fn status_description(&self) -> String {
+        let mut notes = vec![
+            if self.is_identified() { "identified" } else { "not identified" }.to_string(),
+            if self.is_in_compendium() { "in compendium" } else { "not in compendium" }.to_string(),
+        ];
+        if self.typ == ItemType::Armor && self.max_upgrade_level() > 0 {
+            notes.push(format!("level {} of {}", self.armor_level, self.max_upgrade_level()));
+        }
+        if self.count > 0 {
+            notes.push(format!("have {}", self.count));
+        }
+        notes.join("; ")
+    }
+}
+
+impl ContentPack {
+    pub fn variant_to_string<'a>(&self) -> &'a str {
+        match self {
+            ContentPack::Base => "Base",
+            ContentPack::Dlc1 => "DLC1",
+            ContentPack::Dlc2 => "DLC2",
+            ContentPack::FreeDlc => "FreeDLC",
+            ContentPack::Amiibo => "Amiibo",
+        }
+    }
+}
+
+impl ContentOptions {
+    pub fn new_base_game() -> Self {
+        Self {
+            dlc_1: false,
+            dlc_2: false,
+            free_dlc: false,
+            amiibo: false,
+        }
+    }
+
+    pub fn new_all() -> Self {
+        Self {
+            dlc_1: true,
+            dlc_2: true,
+            free_dlc: true,
+            amiibo: true,
+        }
+    }
+
+    pub fn includes(&self, pack: ContentPack) -> bool {
+        match pack {
+            ContentPack::Base => true,
+            ContentPack::Dlc1 => self.dlc_1,
+            ContentPack::Dlc2 => self.dlc_2,
+            ContentPack::FreeDlc => self.free_dlc,
+            ContentPack::Amiibo => self.amiibo,
+        }
+    }
+
+    pub fn includes_all(&self) -> bool {
+        self.dlc_1 && self.dlc_2 && self.free_dlc && self.amiibo
+    }
+}
+
+// Both DLC packs and the free DLC are loaded but Amiibo content isn't, since it can't be
+// collected without the figures.
+impl Default for ContentOptions {
+    fn default() -> Self {
+        Self {
+            dlc_1: true,
+            dlc_2: true,
+            free_dlc: true,
+            amiibo: false,
+        }
+    }
+}
+
+impl ArmorSet {
+    pub fn new(name: &str, set_bonus: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            set_bonus,
+            head: None,
+            body: None,
+            legs: None,
+            shop_locations: vec![],
+            content_pack: ContentPack::Base,
+        }
+    }
+
+    pub fn get_piece(&self, slot: ArmorSlot) -> Option<ItemId> {
+        match slot {
+            ArmorSlot::Head => self.head,
+            ArmorSlot::Body => self.body,
+            ArmorSlot::Legs => self.legs,
+        }
+    }
+
+    pub fn set_piece(&mut self, slot: ArmorSlot, item_id: ItemId) {
+        let piece = match slot {
+            ArmorSlot::Head => &mut self.head,
+            ArmorSlot::Body => &mut self.body,
+            ArmorSlot::Legs => &mut self.legs,
+        };
+        *piece = Some(item_id);
+    }
+
+    // Head, body, and legs in that order, leaving out any that aren't known.
+    pub fn pieces(&self) -> Vec<ItemId> {
+        [ArmorSlot::Head, ArmorSlot::Body, ArmorSlot::Legs].iter().filter_map(|slot| self.get_piece(*slot)).collect()
+    }
+
+    pub fn slot_of(&self, item_id: ItemId) -> Option<ArmorSlot> {
+        [ArmorSlot::Head, ArmorSlot::Body, ArmorSlot::Legs]
+            .iter()
+            .find(|slot| self.get_piece(**slot) == Some(item_id))
+            .copied()
+    }
+}
+
+impl ArmorSlot {
+    // The labels used for the pieces in the Armor Sets section of the Items file.
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.trim() {
+            "head" => Some(ArmorSlot::Head),
+            "body" => Some(ArmorSlot::Body),
+            "legs" => Some(ArmorSlot::Legs),
+            _ => None,
+        }
+    }
+
+    pub fn variant_to_string<'a>(&self) -> &'a str {
+        match self {
+            ArmorSlot::Head => "Head",
+            ArmorSlot::Body => "Body",
+            ArmorSlot::Legs => "Legs",
+        }
+    }
+}
+
+impl AcquisitionSource {
+    pub fn description(&self, model: &Model) -> String {
+        match self {
+            AcquisitionSource::Shop { price, locations, requirements } => {
+                let shop = if locations.is_empty() {
+                    format!("buy for {}", price.description())
+                } else {
+                    let locations = locations.iter().map(|location_id| model.location_name(*location_id)).collect::<Vec<_>>();
+                    format!("buy in {} for {}", locations.join(" or "), price.description())
+                };
+                if requirements.is_empty() {
+                    shop
+                } else {
+                    let requirements = requirements.iter().map(|requirement| requirement.description(model)).collect::<Vec<_>>();
+                    format!("{} once you {}", shop, requirements.join(" and "))
+                }
+            },
+            AcquisitionSource::Quest { quest } => format!("complete the quest {}", model.quest_name(*quest)),
+            AcquisitionSource::Shrine { shrine } => format!("open the chest in {}", model.location_name(*shrine)),
+            AcquisitionSource::Chest { location } => format!("open the chest at {}", model.location_name(*location)),
+            AcquisitionSource::Reach { location } => format!("reach {}", model.location_name(*location)),
+            AcquisitionSource::Milestone { name } => format!("reach the milestone {}", name),
+        }
+    }
+}
+
+impl ItemType {
+    // The subsections of "Items in Order" in the Items file.
+    pub fn from_subsection_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "Money" => Some(ItemType::Money),
+            "Materials" => Some(ItemType::Material),
+            "Weapons" => Some(ItemType::Weapon),
+            "Bows" => Some(ItemType::Bow),
+            "Arrows" => Some(ItemType::Arrow),
+            "Shields" => Some(ItemType::Shield),
+            "Armor" => Some(ItemType::Armor),
+            "Dishes" | "Elixers" | "Roasted Foods" | "Frozen Foods" => Some(ItemType::Food),
+            "Key Items" => Some(ItemType::KeyItem),
+            _ => None,
+        }
+    }
+
+    pub fn variant_to_string<'a>(&self) -> &'a str {
+        match self {
+            ItemType::Money => "Money",
+            ItemType::Weapon => "Weapon",
+            ItemType::Bow => "Bow",
+            ItemType::Arrow => "Arrow",
+            ItemType::Shield => "Shield",
+            ItemType::Armor => "Armor",
+            ItemType::Material => "Material",
+            ItemType::Food => "Food",
+            ItemType::KeyItem => "KeyItem",
+        }
+    }
+}
+
+impl Recipe {
+    pub fn new(name: &str, typ: RecipeType) -> Self {
+        Self {
+            name: name.to_string(),
+            typ,
+            level: None,
+            ingredients: vec![],
+            method: None,
+            content_pack: ContentPack::Base,
+        }
+    }
+
+    // The counts are keyed by lowercase item name. Each slot uses up one of its alternatives, so
+    // two slots that share an alternative need two of that item. Taking the first alternative in
+    // stock for each slot isn't enough, since it can use up an item that a later slot needs, so
+    // this tries the other alternatives before giving up. Recipes have at most five slots.
+    pub fn can_make(&self, counts: &BTreeMap<String, usize>) -> bool {
+        fill_slots(&self.ingredients, &mut counts.clone())
+    }
+}
+
+fn fill_slots(ingredients: &[Ingredient], counts: &mut BTreeMap<String, usize>) -> bool {
+    let (ingredient, rest) = match ingredients.split_first() {
+        Some(split) => split,
+        None => return true,
+    };
+    for key in ingredient.alternatives.iter().map(|alternative| alternative.to_lowercase()) {
+        if let Some(count) = counts.get_mut(&key).filter(|count| **count > 0) {
+            *count -= 1;
+            let filled = fill_slots(rest, counts);
+            *counts.get_mut(&key).unwrap() += 1;
+            if filled {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+impl Ingredient {
+    pub fn new(alternatives: Vec<String>) -> Self {
+        Self {
+            alternatives,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        self.alternatives.join("/")
+    }
+}
+
+impl RecipeType {
+    // The food subsections of "Items in Order" in the Items file.
+    pub fn from_subsection_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "Dishes" => Some(RecipeType::Dish),
+            "Elixers" => Some(RecipeType::Elixir),
+            "Roasted Foods" => Some(RecipeType::Roasted),
+            "Frozen Foods" => Some(RecipeType::Frozen),
+            _ => None,
+        }
+    }
+
+    pub fn variant_to_string<'a>(&self) -> &'a str {
+        match self {
+            RecipeType::Dish => "Dish",
+            RecipeType::Elixir => "Elixir",
+            RecipeType::Roasted => "Roasted",
+            RecipeType::Frozen => "Frozen",
+        }
+    }
+}
+
+impl Price {
+    pub fn quantity_of(&self, item_name: &str) -> usize {
+        self.components
+            .iter()
+            .map(|component| match component {
+                PriceComponent::Item { name, quantity } if name.to_lowercase() == item_name.to_lowercase() => *quantity,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    pub fn description(&self) -> String {
+        self.components.iter().map(|component| component.description()).collect::<Vec<String>>().join(", ")
+    }
+}
+
+impl PriceComponent {
+    pub fn name(&self) -> &str {
+        match self {
+            PriceComponent::Item { name, .. } => name,
+            PriceComponent::Requirement { name } => name,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            PriceComponent::Item { name, quantity } => format!("{} {}", quantity, name),
+            PriceComponent::Requirement { name } => name.to_string(),
+        }
+    }
+}
+
+impl Effect {
+    pub fn from_description(description: &str) -> Option<Self> {
+        for (substring, effect) in [
+            ("additional hearts", Effect::TemporaryHearts),
+            ("hearts restored", Effect::RestoreHearts),
+            ("attack", Effect::Attack),
+            ("defense", Effect::Defense),
+            ("temporary stamina", Effect::TemporaryStamina),
+            ("stamina boost", Effect::TemporaryStamina),
+            ("health boost", Effect::TemporaryHearts),
+            ("fireproof", Effect::Fireproof),
+            ("stamina restor", Effect::RestoreStamina),
+            ("heat", Effect::HeatResistance),
+            ("cold", Effect::ColdResistance),
+            ("electric", Effect::ShockResistance),
+            ("movement", Effect::MovementSpeed),
+            ("stealth", Effect::Stealth)
+        ].iter() {
+            if description.contains(substring) {
+                return Some(*effect);
+            }
+        }
+        None
+    }
+
+    pub fn variant_to_string<'a>(&self) -> &'a str {
+        match self {
+            Effect::RestoreHearts => "RestoreHearts",
+            Effect::TemporaryHearts => "TemporaryHearts",
+            Effect::RestoreStamina => "RestoreStamina",
+            Effect::TemporaryStamina => "TemporaryStamina",
+            Effect::MovementSpeed => "MovementSpeed",
+            Effect::Fireproof => "Fireproof",
+            Effect::ColdResistance => "ColdResistance",
+            Effect::HeatResistance => "HeatResistance",
+            Effect::ShockResistance => "ShockResistance",
+            Effect::Attack => "Attack",
+            Effect::Defense => "Defense",
+            Effect::Fire => "Fire",
+            Effect::Cold => "Cold",
+            Effect::Shock => "Shock",
+            Effect::NightMovementSpeed => "NightMovementSpeed",
+            Effect::SwimmingSpeed => "SwimmingSpeed",
+            Effect::ClimbingSpeed => "ClimbingSpeed",
+            Effect::Disguise => "Disguise",
+            Effect::Stealth => "Stealth",
+        }
+    }
+}
+
 impl QuestType {
     pub fn variant_to_string<'a>(&self) -> &'a str {
         match self {
@@ -654,14 +1838,6 @@ pub fn describe(&self) {
 }
 */
 
-    /*
-    pub fn items_in_order(&self) -> Vec<&Item> {
-        let mut v: Vec<&Item> = self.items.values().collect();
-        v.sort_by_key(|item| item.sequence);
-        v
-    }
-    */
-
     /*
     pub fn describe_deep(&self, s: &mut String, depth: usize, _max_depth: Option<usize>) {
         s.push_str(&format_indent_line_space(depth, "Botw Model"));
@@ -966,3 +2142,171 @@ mod tests {
 }
 */
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use super::super::data_source;
+
+    // Descriptions in the Item Effects section that don't name an effect.
+    const DESCRIPTIONS_WITHOUT_EFFECT: [&str; 8] = [
+        "Attach to objects to make them float",
+        "Cook to with ingredients to make an elixir",
+        "Cook to with ingredients to make an elixir or use in armor",
+        "ingredient for special recipes",
+        "ingredient in special recipes",
+        "Use at labs to make ancient armor or weapons",
+        "Use to sell or in armor",
+        "Use with Wood to make fire",
+    ];
+
+    #[test]
+    fn every_effect_description_gives_an_effect() {
+        let source = DataSource::new_embedded();
+        let model = Model::load_text(&source, &ContentOptions::default()).unwrap();
+        let sections = parse::read_file_into_sections(&source, parse::FILE_NAME_ITEMS, parse::PREFIX_HEADER).unwrap();
+        let mut line_count = 0;
+        for line in parse::section_to_lines(sections["Item Effects"].clone(), &ContentOptions::default()) {
+            if let (name, Some(description)) = util::parse::split_1_or_2(&line, "\t") {
+                if !DESCRIPTIONS_WITHOUT_EFFECT.contains(&description.trim()) {
+                    assert!(model.get_item(name).effect.is_some(), "\"{}\" has no effect for \"{}\".", name, description);
+                    line_count += 1;
+                }
+            }
+        }
+        assert!(line_count > 80);
+    }
+
+    #[test]
+    fn elixir_descriptions() {
+        assert_eq!(Some(Effect::Fireproof), Effect::from_description("fireproof elixir"));
+        assert_eq!(Some(Effect::TemporaryStamina), Effect::from_description("stamina boost elixir"));
+        assert_eq!(Some(Effect::TemporaryHearts), Effect::from_description("health boost elixir"));
+        assert_eq!(Some(Effect::RestoreStamina), Effect::from_description("stamina restoration elixir"));
+        assert_eq!(None, Effect::from_description("Use to sell or in armor"));
+    }
+
+    #[test]
+    fn acquisition_sources_from_prices() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::new_all()).unwrap();
+        let source = |item_name: &str| model.acquisition_source(item_name).unwrap();
+        match source("Monster Saddle") {
+            AcquisitionSource::Shop { price, requirements, .. } => {
+                assert_eq!(price.description(), "299 Mon");
+                assert!(matches!(requirements.as_slice(), [AcquisitionSource::Milestone { name }] if *name == QUEST_DEFEAT_TWO_DIVINE_BEASTS));
+            },
+            other => panic!("{:?}", other),
+        }
+        assert!(source("Monster Saddle").description(&model).ends_with("once you reach the milestone Defeat Two Divine Beasts"));
+        assert!(matches!(source("Tingle's Tights"), AcquisitionSource::Quest { quest } if model.quest_name(*quest) == "EX Treasure: Fairy Clothes"));
+        assert!(matches!(source("Island Lobster Shirt"), AcquisitionSource::Quest { quest } if model.quest_name(*quest) == "EX Treasure: Garb of Winds"));
+        assert!(matches!(source("Climbing Gear"), AcquisitionSource::Shrine { shrine } if model.location_name(*shrine) == "Chaas Qeta Shrine"));
+        assert!(matches!(source("Zora Armor"), AcquisitionSource::Reach { location } if model.location_name(*location) == "Zora's Domain"));
+        assert!(matches!(source("Hylian Tunic"), AcquisitionSource::Shop { requirements, .. } if requirements.is_empty()));
+    }
+
+    #[test]
+    fn every_armor_set_with_a_price_has_a_shop() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::new_all()).unwrap();
+        for item in model.items.values().filter(|item| item.typ == ItemType::Armor) {
+            if let (Some(AcquisitionSource::Shop { locations, .. }), Some(armor_set)) = (&item.acquisition_source, model.get_armor_set_for_item(&item.name)) {
+                assert!(!locations.is_empty(), "{} in {}", item.name, armor_set.name);
+            }
+        }
+        assert_eq!(model.acquisition_source("Hylian Tunic").unwrap().description(&model), "buy in Hateno Village or Tarrey Town for 120 Rupee");
+    }
+
+    #[test]
+    fn unknown_requirement_is_a_load_error() {
+        let path = data_source::copy_embedded_files_to_temp("model_unknown_requirement");
+        let items_path = path.join(parse::FILE_NAME_ITEMS);
+        let items = fs::read_to_string(&items_path).unwrap().replace("Champion's Tunic: Locked Mementos", "Champion's Tunic: Not A Quest");
+        fs::write(&items_path, items).unwrap();
+        let errors = Model::load_text(&DataSource::new_directory(&path), &ContentOptions::default()).unwrap_err();
+        assert!(errors.iter().any(|error| error.file_name == parse::FILE_NAME_ITEMS && error.line == "Champion's Tunic" && error.expected.contains("Not A Quest")), "{:?}", errors);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_armor_includes_pieces_outside_sets() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        let mut inventory = BTreeMap::new();
+        inventory.insert("Hylian Tunic".to_string(), 1);
+        let missing = model.missing_armor(&inventory);
+        let armor_count = model.items.values().filter(|item| item.typ == ItemType::Armor).count();
+        assert_eq!(missing.len(), armor_count - 1);
+        assert!(missing.iter().all(|(_, item, _)| item.name != "Hylian Tunic"));
+        assert!(missing.iter().any(|(armor_set, item, _)| item.name == "Amber Earrings" && armor_set.is_none()));
+        assert!(missing.iter().any(|(armor_set, item, _)| item.name == "Hylian Hood" && armor_set.map(|armor_set| armor_set.name.as_str()) == Some("Hylian Set")));
+    }
+
+    #[test]
+    fn ambiguous_alias_is_reported() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        assert_eq!(model.try_get_item("glider").unwrap().name, "Paraglider");
+        match model.try_item_id("Lynel gear") {
+            Err(LookupError::Ambiguous { names, .. }) => assert!(names.len() > 1),
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(model.try_get_recipe("Lynel gear"), Err(LookupError::Unknown { .. })));
+        assert_eq!(model.try_get_recipe("fried bananas").unwrap().name, "Fried Bananas");
+    }
+
+    fn counts(items: &[(&str, usize)]) -> BTreeMap<String, usize> {
+        items.iter().map(|(name, count)| (name.to_lowercase(), *count)).collect()
+    }
+
+    fn recipe(slots: &[&str]) -> Recipe {
+        let mut recipe = Recipe::new("Test Recipe", RecipeType::Dish);
+        recipe.ingredients = slots.iter().map(|slot| Ingredient::new(slot.split('/').map(|name| name.to_string()).collect())).collect();
+        recipe
+    }
+
+    #[test]
+    fn can_make_tries_other_alternatives() {
+        // Putting the A in the first slot leaves nothing for the second.
+        let recipe = recipe(&["A/B", "A"]);
+        assert!(recipe.can_make(&counts(&[("A", 1), ("B", 1)])));
+        assert!(!recipe.can_make(&counts(&[("A", 1)])));
+        assert!(!recipe.can_make(&counts(&[("B", 2)])));
+    }
+
+    #[test]
+    fn can_make_counts_shared_items() {
+        let recipe = recipe(&["Apple", "Apple", "Hylian Shroom/Endura Shroom"]);
+        assert!(!recipe.can_make(&counts(&[("Apple", 1), ("Endura Shroom", 1)])));
+        assert!(recipe.can_make(&counts(&[("apple", 2), ("Endura Shroom", 1)])));
+        assert!(!recipe.can_make(&counts(&[])));
+        assert!(Recipe::new("Nothing", RecipeType::Dish).can_make(&counts(&[])));
+    }
+
+    fn inventory(items: &[(&str, usize)]) -> BTreeMap<String, usize> {
+        items.iter().map(|(name, count)| (name.to_string(), *count)).collect()
+    }
+
+    #[test]
+    fn mon_value_uses_the_mon_sell_prices() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        // Bokoblin Horns sell for 3 Mon and Octo Balloons for 1. Apples and unknown names are ignored.
+        assert_eq!(7, model.mon_value(&inventory(&[("Bokoblin Horn", 2), ("octo balloon", 1), ("Apple", 5), ("Not An Item", 3)])));
+        assert_eq!(0, model.mon_value(&inventory(&[])));
+    }
+
+    #[test]
+    fn can_afford_in_mon_leaves_out_what_the_price_takes() {
+        let mut model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        // The Bokoblin Mask costs 99 Mon.
+        assert!(model.can_afford_in_mon("Bokoblin Mask", &inventory(&[("Mon", 90), ("Bokoblin Horn", 3)])).unwrap());
+        assert!(!model.can_afford_in_mon("Bokoblin Mask", &inventory(&[("Mon", 90), ("Bokoblin Horn", 2)])).unwrap());
+        assert!(matches!(model.can_afford_in_mon("Not An Item", &inventory(&[])), Err(LookupError::Unknown { .. })));
+
+        // With three of the horns going into the price, they can't also be sold to make up the Mon.
+        model.get_item_mut("Bokoblin Mask").purchase_price = Some(Price { components: vec![
+            PriceComponent::Item { name: ITEM_MON.to_string(), quantity: 90 },
+            PriceComponent::Item { name: "Bokoblin Horn".to_string(), quantity: 3 },
+        ] });
+        assert!(!model.can_afford_in_mon("Bokoblin Mask", &inventory(&[("Mon", 85), ("Bokoblin Horn", 4)])).unwrap());
+        assert!(model.can_afford_in_mon("Bokoblin Mask", &inventory(&[("Mon", 85), ("Bokoblin Horn", 5)])).unwrap());
+    }
+}
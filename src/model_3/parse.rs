@@ -1,9 +1,13 @@
-use std::io::{BufReader, BufRead};
-use std::fs::File;
 use std::str::FromStr;
+use std::fmt;
+use std::collections::BTreeSet;
+use serde::{Deserialize, Serialize};
 
 use crate::*;
 use super::model::*;
+use super::alias::AliasList;
+use super::arena::*;
+use super::data_source::DataSource;
 use util::parse;
 
 pub const PREFIX_HEADER: &str = "#";
@@ -15,48 +19,170 @@ pub const SUFFIX_SHRINE: &str = " Shrine";
 pub const SUFFIX_STABLE: &str = " Stable";
 pub const SUFFIX_TOWER: &str = " Tower";
 pub const SUFFIX_TECH_LAB: &str = " Tech Lab";
-#[allow(dead_code)]
-const FILE_NAME_CHARACTERS: &str = "Breath of the Wild Characters.txt";
-#[allow(dead_code)]
-const FILE_NAME_ITEMS: &str = "Breath of the Wild Items.txt";
-#[allow(dead_code)]
-const FILE_NAME_INVENTORY: &str = "Breath of the Wild Inventory.txt";
-#[allow(dead_code)]
-const FILE_NAME_LOCATIONS: &str = "Breath of the Wild Locations.txt";
-#[allow(dead_code)]
-const FILE_NAME_DOG_TREASURES: &str = "Breath of the Wild Dog Treasures.txt";
-#[allow(dead_code)]
-const FILE_NAME_SHRINES: &str = "Breath of the Wild Shrines.txt";
-#[allow(dead_code)]
-const FILE_NAME_QUESTS: &str = "Breath of the Wild Quests.txt";
-const AMIIBO: &str = "Amiibo";
-const SUFFIX_DLC: &str = " (DLC)";
-const SUFFIX_FREE_DLC: &str = " (Free DLC)";
-const READ_AMIIBO: bool = false;
-
-pub fn load_characters(model: &mut Model) {
-    let file = File::open(FILE_NAME_CHARACTERS).unwrap();
-    let reader = BufReader::new(file);
+pub const PREFIX_MAP_LOCATION: &str = "{";
+pub const FILE_NAME_CHARACTERS: &str = "Breath of the Wild Characters.txt";
+pub const FILE_NAME_ITEMS: &str = "Breath of the Wild Items.txt";
+pub const FILE_NAME_INVENTORY: &str = "Breath of the Wild Inventory.txt";
+pub const FILE_NAME_LOCATIONS: &str = "Breath of the Wild Locations.txt";
+pub const FILE_NAME_DOG_TREASURES: &str = "Breath of the Wild Dog Treasures.txt";
+pub const FILE_NAME_SHRINES: &str = "Breath of the Wild Shrines.txt";
+pub const FILE_NAME_QUESTS: &str = "Breath of the Wild Quests.txt";
+pub const FILE_NAME_MAP_LOCATIONS: &str = "Breath of the Wild Map Locations for Completion Raw.txt";
+pub const FILE_NAME_ALIASES: &str = "Breath of the Wild Aliases.txt";
+pub const FILE_NAME_USER_ALIASES: &str = "Breath of the Wild User Aliases.txt";
+pub const SECTION_ITEMS_IN_ORDER: &str = "Items in Order";
+pub const SECTION_ITEM_EFFECTS: &str = "Item Effects";
+pub const SECTION_PURCHASE_AND_UPGRADE: &str = "Purchase and Upgrade";
+pub const SECTION_MON_SELL_PRICES: &str = "Mon Sell Prices";
+pub const SECTION_ARMOR_SETS: &str = "Armor Sets";
+pub const ARMOR_SET_SHOP: &str = "shop";
+pub const DRAGON_PLACEHOLDER: &str = "Dragon";
+pub const DRAGON_NAMES: [&str; 3] = ["Dinraal", "Farosh", "Naydra"];
+const PREFIX_RECIPE_LEVEL: &str = "Lv. ";
+const RECIPE_METHODS: [&str; 3] = ["set on fire.", "frozen.", "placed in boiling water."];
+pub const PHRASE_MONSTER_PART: &str = "Cook to with ingredients";
+pub const SEPARATOR_COLUMN: &str = "\t";
+// Side quest notes are separated by semicolons, as in
+// "Cooking with Koko (Requires Koko’s Kitchen; giver: Koko; location: Kakariko Village)".
+pub const SEPARATOR_QUEST_NOTES: &str = ";";
+pub const QUEST_NOTE_GIVER: &str = "giver";
+pub const QUEST_NOTE_LOCATION: &str = "location";
+pub const QUEST_NOTE_REGION: &str = "region";
+// Markers after a name in the catalog files showing which content pack it belongs to, as in
+// "Tingle's Hood (DLC1)". A plain " (DLC)" is from older copies of the files.
+const CONTENT_PACK_MARKERS: [(&str, ContentPack); 5] = [
+    (" (DLC1)", ContentPack::Dlc1),
+    (" (DLC2)", ContentPack::Dlc2),
+    (" (DLC)", ContentPack::Dlc1),
+    (" (Free DLC)", ContentPack::FreeDlc),
+    (" (Amiibo)", ContentPack::Amiibo),
+];
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub file_name: String,
+    pub line_number: Option<usize>,
+    pub line: String,
+    pub expected: String,
+}
+
+// One entry in the datamined map file. A typical line is:
+//   {"internal_name":"Location_DeathMountain_Entrance", "display_name":"Maw of Death Mountain", "x":2402.58, "y":-1320.01},
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MapLocationEntry {
+    pub internal_name: String,
+    pub display_name: String,
+    pub x: f64,
+    pub y: f64,
+}
+
+// Names that couldn't be joined between the map file and the Locations catalog.
+#[derive(Debug, Default)]
+pub struct MapLocationMatch {
+    // Map entries whose display name isn't a known location.
+    pub unmatched_map_names: Vec<String>,
+    // Map entries for a location that an earlier entry was already joined to.
+    pub duplicate_map_names: Vec<String>,
+    pub unmatched_location_names: Vec<String>,
+}
+
+impl ParseError {
+    pub fn new(file_name: &str, line_number: Option<usize>, line: &str, expected: &str) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            line_number,
+            line: line.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line_number {
+            Some(line_number) => write!(f, "{}, line {}: expected {}, found \"{}\".", self.file_name, line_number, self.expected, self.line),
+            None => write!(f, "{}: expected {} ({}).", self.file_name, self.expected, self.line),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn print_errors(errors: &[ParseError]) {
+    println!("{} parse error(s):", errors.len());
+    for error in errors.iter() {
+        println!("\t{}", error);
+    }
+}
+
+pub fn read_file(source: &DataSource, file_name: &str) -> Result<String, Vec<ParseError>> {
+    source.read_to_string(file_name)
+        .map_err(|e| vec![ParseError::new(file_name, None, &format!("{}: {}", source.description(), e), "a readable file")])
+}
+
+// Returns the trimmed, non-empty, non-comment lines of the file along with their 1-based line
+// numbers so that errors can point back to the original text.
+fn read_lines(source: &DataSource, file_name: &str) -> Result<Vec<(usize, String)>, Vec<ParseError>> {
+    let content = read_file(source, file_name)?;
+    Ok(content.lines()
+        .enumerate()
+        .map(|(line_index, line)| (line_index + 1, line.trim().to_string()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with(PREFIX_COMMENT))
+        .collect())
+}
+
+fn errors_to_result(errors: Vec<ParseError>) -> Result<(), Vec<ParseError>> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn load_characters(model: &mut Model, source: &DataSource) -> Result<(), Vec<ParseError>> {
+    let file_name = FILE_NAME_CHARACTERS;
+    let mut errors = vec![];
     let mut race = None;
-    for line in reader.lines()
-            .map(|line| line.unwrap().trim().to_string())
-            .filter(|line| !line.is_empty())
-            .filter(|line| !line.starts_with(PREFIX_COMMENT)) {
+    let mut skip_section = false;
+    for (line_number, line) in read_lines(source, file_name)? {
         //rintln!("{}", line);
+        let (line, content_pack) = split_content_pack(&line);
         if line.starts_with(PREFIX_HEADER) {
             let race_name = line.replace(PREFIX_HEADER, "");
-            race = Some(Race::from_str(&race_name.replace(" ", "")).unwrap());
-        } else {
+            race = Race::from_str(&race_name.replace(" ", "")).ok();
+            if race.is_none() {
+                errors.push(ParseError::new(file_name, Some(line_number), &line, "a race header such as \"#Hylian\""));
+            }
+            // Only report the bad header, not every character under it.
+            skip_section = race.is_none();
+        } else if !skip_section {
             let (first, second) = parse::split_1_or_2(&line, ":");
             let (main, champion, merchant, alive) = if let Some(tags) = second {
                 (tags.contains("main"), tags.contains("champion"), tags.contains("merchant"), !tags.contains("dead"))
             } else {
                 (false, false, false, true)
             };
-            let name = first;
-            model.add_character(Character::new(name, &(race.as_ref().unwrap().clone()), main, champion, merchant, alive));
+            let name = first.trim();
+            match &race {
+                Some(race) => {
+                    // Everyone under the Amiibo header comes with a figure.
+                    let content_pack = if *race == Race::Amiibo { ContentPack::Amiibo } else { content_pack };
+                    if !model.content_options.includes(content_pack) {
+                        continue;
+                    }
+                    if model.characters.contains_key(&name.to_lowercase()) {
+                        errors.push(ParseError::new(file_name, Some(line_number), &line, "a character name that is not already defined"));
+                    } else {
+                        let mut character = Character::new(name, race, main, champion, merchant, alive);
+                        character.content_pack = content_pack;
+                        model.add_character(character);
+                    }
+                },
+                None => errors.push(ParseError::new(file_name, Some(line_number), &line, "a character under a valid race header")),
+            }
         }
     }
+    errors_to_result(errors)
 }
 
 /*
@@ -75,113 +201,172 @@ pub fn load_shrines(model: &mut Model) {
 }
 */
 
-pub fn read_file_into_sections(file_name: &str, header_prefix: &str) -> BTreeMap<String, String> {
-    let content = fs::read_to_string(file_name).unwrap();
-    break_into_sections(content, header_prefix)
+pub fn read_file_into_sections(source: &DataSource, file_name: &str, header_prefix: &str) -> Result<BTreeMap<String, String>, Vec<ParseError>> {
+    let content = read_file(source, file_name)?;
+    Ok(break_into_sections(content, header_prefix))
 }
 
 pub fn break_into_sections(content: String, header_prefix: &str) -> BTreeMap<String, String> {
+    // Only a prefix at the start of a line begins a section, since the prefix may also appear in
+    // the middle of a line such as in a URL in a comment.
     let mut map = BTreeMap::new();
-    // dbg!(&content.split(&format!("\n{}", header_prefix)));
-    // for s in content.split(&format!("\r\n{}", header_prefix)) {
-    for split in content.split(header_prefix) {
-        // println!("|{}|", &s);
-
-
-        // let mut section_split = split.splitn(2, "\n");
-        // let header = section_split.next().unwrap();
-        //rintln!("|{}|", &header);
-        // let section_content = section_split.next().unwrap();
-
-        let (header, section_content) = parse::split_2(split, "\n");
-        map.insert(header.to_string(), section_content.to_string());
+    let mut header = "".to_string();
+    let mut section_content = "".to_string();
+    for line in content.lines() {
+        if line.starts_with(header_prefix) {
+            map.insert(header, section_content);
+            header = line.replacen(header_prefix, "", 1).trim().to_string();
+            section_content = "".to_string();
+        } else {
+            section_content.push_str(line);
+            section_content.push('\n');
+        }
     }
+    map.insert(header, section_content);
     map
 }
 
-pub fn section_to_lines(content: String) -> Vec<String> {
+// Drops the lines for content packs that aren't included and strips the markers from the rest.
+pub fn section_to_lines(content: String, content_options: &ContentOptions) -> Vec<String> {
     content.split("\n")
         .map(|line| line.trim())
-        .filter(|line| line.len() > 0
-            && !line.starts_with(PREFIX_COMMENT)
-            && (READ_AMIIBO || !line.contains(AMIIBO)))
-        .map(|line| line.replace(SUFFIX_DLC, "").replace(SUFFIX_FREE_DLC, "").to_string())
+        .filter(|line| !line.is_empty() && !line.starts_with(PREFIX_COMMENT))
+        .map(split_content_pack)
+        .filter(|(_, content_pack)| content_options.includes(*content_pack))
+        .map(|(line, _)| line)
         .collect()
 }
 
-pub fn load_locations(model: &mut Model) {
-    let file = File::open(FILE_NAME_LOCATIONS).unwrap();
-    let reader = BufReader::new(file);
+// Returns the line without its content pack marker, if any, and the pack it names. The marker may
+// be followed by more of the line as in "Tingle's Tights (DLC1): EX Treasure: Fairy Clothes".
+pub fn split_content_pack(line: &str) -> (String, ContentPack) {
+    for (marker, content_pack) in CONTENT_PACK_MARKERS.iter() {
+        if line.contains(marker) {
+            return (line.replacen(marker, "", 1), *content_pack);
+        }
+    }
+    (line.to_string(), ContentPack::Base)
+}
+
+// The marker written after a name for the content pack, or nothing for the base game.
+pub fn content_pack_marker<'a>(content_pack: ContentPack) -> &'a str {
+    CONTENT_PACK_MARKERS
+        .iter()
+        .find(|(_, marker_content_pack)| *marker_content_pack == content_pack)
+        .map_or("", |(marker, _)| marker)
+}
+
+// A location without a marker belongs to the same pack as the region or area it's in.
+fn inherit_content_pack(content_pack: ContentPack, parent_content_pack: Option<ContentPack>) -> ContentPack {
+    match (content_pack, parent_content_pack) {
+        (ContentPack::Base, Some(parent_content_pack)) => parent_content_pack,
+        _ => content_pack,
+    }
+}
+
+pub fn load_locations(model: &mut Model, source: &DataSource) -> Result<(), Vec<ParseError>> {
+    let file_name = FILE_NAME_LOCATIONS;
+    let mut errors = vec![];
     let mut region_name = None;
     let mut area_name = None;
-    for line in reader.lines()
-        .map(|line| line.unwrap().trim().to_string())
-        .filter(|line| !line.is_empty())
-        .filter(|line| !line.starts_with(PREFIX_COMMENT)) {
+    // The IDs are None when the region or area wasn't added, as when its content pack isn't
+    // included.
+    let mut region_id = None;
+    let mut area_id = None;
+    let mut region_content_pack = None;
+    let mut area_content_pack = None;
+    for (line_number, line) in read_lines(source, file_name)? {
         //rintln!("{}", line);
+        let (line, content_pack) = split_content_pack(&line);
         if line.starts_with(PREFIX_HEADER) {
             let name = line.replace(PREFIX_HEADER, "");
             region_name = Some(name.to_string());
-            model.add_location(Location::new(&name, LocationType::Region, None));
+            area_name = None;
+            region_content_pack = Some(content_pack);
+            area_content_pack = None;
+            region_id = add_location_checked(model, &mut errors, file_name, line_number, &line, Location::new(&name, LocationType::Region, None), content_pack);
+            area_id = None;
         } else if line.starts_with(PREFIX_SUBHEADER) {
             let (name, location_type) = if line.ends_with(SUFFIX_TOWN) {
                 (line.replace(SUFFIX_TOWN, ""), LocationType::Town)
             } else {
-                (line, LocationType::Area)
+                (line.clone(), LocationType::Area)
             };
-            assert!(region_name.is_some());
+            if region_name.is_none() {
+                errors.push(ParseError::new(file_name, Some(line_number), &line, "an area under a \"#\" region header"));
+                continue;
+            }
             let name = name.replace(PREFIX_SUBHEADER, "");
+            let content_pack = inherit_content_pack(content_pack, region_content_pack);
             area_name = Some(name.to_string());
-            model.add_location(Location::new(&name, location_type, region_name.clone()));
+            area_content_pack = Some(content_pack);
+            // An area can't be added under a region that was left out, or it would show up as
+            // another region. Everything under the area is skipped along with it.
+            area_id = match region_id {
+                Some(region_id) => add_location_checked(model, &mut errors, file_name, line_number, &line, Location::new(&name, location_type, Some(region_id)), content_pack),
+                None => None,
+            };
         } else {
             let (name, location_type) = if line.ends_with(SUFFIX_TOWN) {
                 (line.replace(SUFFIX_TOWN, ""), LocationType::Town)
             } else if line.ends_with(SUFFIX_SHRINE) {
-                (line, LocationType::Shrine)
+                (line.clone(), LocationType::Shrine)
             } else if line.ends_with(SUFFIX_TOWER) {
-                (line, LocationType::Tower)
+                (line.clone(), LocationType::Tower)
             } else if line.ends_with(SUFFIX_TECH_LAB) {
-                (line, LocationType::TechLab)
+                (line.clone(), LocationType::TechLab)
             } else if line.ends_with(SUFFIX_STABLE) {
-                (line, LocationType::Stable)
+                (line.clone(), LocationType::Stable)
             } else {
-                (line, LocationType::Normal)
+                (line.clone(), LocationType::Normal)
             };
-            assert!(area_name.is_some());
-            model.add_location(Location::new(&name, location_type, area_name.clone()));
+            if area_name.is_none() {
+                errors.push(ParseError::new(file_name, Some(line_number), &line, "a location under a \">\" area header"));
+                continue;
+            }
+            let content_pack = inherit_content_pack(content_pack, area_content_pack);
+            if let Some(area_id) = area_id {
+                add_location_checked(model, &mut errors, file_name, line_number, &line, Location::new(&name, location_type, Some(area_id)), content_pack);
+            }
         }
     }
-    add_location_parent_references(model);
-    load_dog_treasures(model);
-    load_shrines(model);
+    if let Err(mut dog_treasure_errors) = load_dog_treasures(model, source) {
+        errors.append(&mut dog_treasure_errors);
+    }
+    if let Err(mut shrine_errors) = load_shrines(model, source) {
+        errors.append(&mut shrine_errors);
+    }
+    errors_to_result(errors)
 }
 
-fn add_location_parent_references(model: &mut Model) {
-    let mut child_to_parent: BTreeMap<String, String> = BTreeMap::new();
-    for parent_location in model.locations.values() {
-        let parent_name = parent_location.name.clone();
-        for child_name in parent_location.child_locations.iter() {
-            assert_ne!(&parent_name, child_name);
-            child_to_parent.insert(child_name.to_string(), parent_location.name.clone());
-        }
+fn add_location_checked(model: &mut Model, errors: &mut Vec<ParseError>, file_name: &str, line_number: usize, line: &str, mut location: Location, content_pack: ContentPack) -> Option<LocationId> {
+    if !model.content_options.includes(content_pack) {
+        return None;
     }
-    for child_location in model.locations.values_mut() {
-        if let Some(parent_name) = child_to_parent.get(&child_location.name) {
-            child_location.parent_location = Some(parent_name.clone())
-        }
+    if model.locations.contains_key(&location.name.to_lowercase()) {
+        errors.push(ParseError::new(file_name, Some(line_number), line, "a location name that is not already defined"));
+        None
+    } else {
+        location.content_pack = content_pack;
+        Some(model.add_location(location))
     }
 }
 
-fn load_dog_treasures(model: &mut Model) {
-    let file = File::open(FILE_NAME_DOG_TREASURES).unwrap();
-    let reader = BufReader::new(file);
-    for line in reader.lines()
-        .map(|line| line.unwrap().trim().to_string())
-        .filter(|line| !line.is_empty()) {
+fn load_dog_treasures(model: &mut Model, source: &DataSource) -> Result<(), Vec<ParseError>> {
+    let file_name = FILE_NAME_DOG_TREASURES;
+    let mut errors = vec![];
+    for (line_number, line) in read_lines(source, file_name)? {
         //rintln!("{}", line);
-
-        let (name, treasure) = parse::split_2(&line, ": ");
-        model.get_location_mut(name).dog_treasure = Some(treasure.to_string());
+        match parse::split_1_or_2(&line, ": ") {
+            (name, Some(treasure)) => {
+                match model.locations.get_mut(&name.trim().to_lowercase()) {
+                    Some(location) if location.dog_treasure.is_some() => errors.push(ParseError::new(file_name, Some(line_number), &line, "a location that is not already listed")),
+                    Some(location) => location.dog_treasure = Some(treasure.trim().to_string()),
+                    None => errors.push(ParseError::new(file_name, Some(line_number), &line, "the name of a known location before the colon")),
+                }
+            },
+            _ => errors.push(ParseError::new(file_name, Some(line_number), &line, "\"location: treasure\"")),
+        }
     }
     /*
     // Show the locations with dog treasures.
@@ -192,68 +377,542 @@ fn load_dog_treasures(model: &mut Model) {
         dbg!(location);
     }
     */
+    errors_to_result(errors)
 }
 
-fn load_shrines(model: &mut Model) {
-    let file = File::open(FILE_NAME_SHRINES).unwrap();
-    let reader = BufReader::new(file);
-    for line in reader.lines()
-        .map(|line| line.unwrap().trim().to_string())
-        .filter(|line| !line.is_empty())
-        .filter(|line| !line.starts_with(PREFIX_COMMENT)) {
+fn load_shrines(model: &mut Model, source: &DataSource) -> Result<(), Vec<ParseError>> {
+    let file_name = FILE_NAME_SHRINES;
+    let mut errors = vec![];
+    for (line_number, line) in read_lines(source, file_name)? {
+        match parse::split_1_or_2(&line, ":") {
+            (name, Some(challenge)) => {
+                let name = name.trim();
+                let challenge_value = challenge.trim().to_string();
+                match model.locations.get_mut(&name.to_lowercase()) {
+                    Some(location) if location.challenge.is_some() => errors.push(ParseError::new(file_name, Some(line_number), &line, "a shrine that is not already listed")),
+                    Some(location) if location.typ == LocationType::Shrine => location.challenge = Some(challenge_value),
+                    _ => errors.push(ParseError::new(file_name, Some(line_number), &line, "the name of a known shrine before the colon")),
+                }
+            },
+            _ => errors.push(ParseError::new(file_name, Some(line_number), &line, "\"shrine: challenge\"")),
+        }
+    }
+    // Shrines with no challenge are reported by validate::validate_model().
+    errors_to_result(errors)
+}
 
-        let (name, challenge) = parse::split_2(&line, ":");
-        let name = name.trim();
-        let challenge_value = challenge.trim().to_string();
+// The key of the location that the map entry is for. An internal name with an alias in the
+// Aliases file names the location, since several entries can have the same display name and some
+// only have an internal name. Otherwise the display name is the location's name.
+fn map_entry_key(model: &Model, entry: &MapLocationEntry) -> String {
+    match model.aliases.get(AliasList::Location, &entry.internal_name) {
+        [key] => key.clone(),
+        _ => entry.display_name.trim().to_lowercase(),
+    }
+}
 
-        model.get_shrine_mut(name).challenge = Some(challenge_value);
+// Adds the internal name and map coordinates from the datamined map file to the matching
+// locations. This comes after the aliases are loaded since some entries are joined through them.
+pub fn load_map_locations(model: &mut Model, source: &DataSource) -> Result<MapLocationMatch, Vec<ParseError>> {
+    let entries = read_map_locations(source)?;
+    model.map_entries = entries.clone();
+    for entry in entries.iter() {
+        let key = map_entry_key(model, entry);
+        if let Some(location) = model.locations.get_mut(&key) {
+            if location.internal_name.is_none() {
+                location.internal_name = Some(entry.internal_name.clone());
+                location.map_point = Some(MapPoint::new(entry.x, entry.y));
+                let name = location.name.clone();
+                model.aliases.add(AliasList::Location, &entry.internal_name, &name);
+            }
+        }
     }
-    for location in model
-        .locations
+    Ok(match_map_locations(model, &entries))
+}
+
+// Reports the names that don't match between the map file and a model that has already been
+// loaded, without changing the model. A model loaded from the text catalog already has this as
+// map_location_match.
+pub fn check_map_locations(model: &Model, source: &DataSource) -> Result<MapLocationMatch, Vec<ParseError>> {
+    let entries = read_map_locations(source)?;
+    Ok(match_map_locations(model, &entries))
+}
+
+fn read_map_locations(source: &DataSource) -> Result<Vec<MapLocationEntry>, Vec<ParseError>> {
+    let file_name = FILE_NAME_MAP_LOCATIONS;
+    let mut errors = vec![];
+    let mut entries = vec![];
+    for (line_number, line) in read_lines(source, file_name)?
+            .into_iter()
+            .filter(|(_, line)| line.starts_with(PREFIX_MAP_LOCATION)) {
+        match serde_json::from_str(line.trim_end_matches(',')) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => errors.push(ParseError::new(file_name, Some(line_number), &line, "{\"internal_name\":..., \"display_name\":..., \"x\":..., \"y\":...}")),
+        }
+    }
+    if errors.is_empty() {
+        Ok(entries)
+    } else {
+        Err(errors)
+    }
+}
+
+// A map entry matches if it's the one whose internal name was stored on the location. Later
+// entries with the same display name don't.
+fn match_map_locations(model: &Model, entries: &[MapLocationEntry]) -> MapLocationMatch {
+    let mut map_match = MapLocationMatch::default();
+    for entry in entries.iter() {
+        match model.locations.get(&map_entry_key(model, entry)) {
+            Some(location) if location.internal_name.as_ref() == Some(&entry.internal_name) => {},
+            Some(_) => map_match.duplicate_map_names.push(entry.display_name.clone()),
+            None => map_match.unmatched_map_names.push(entry.display_name.clone()),
+        }
+    }
+    map_match.unmatched_location_names = model.locations
         .values()
-        .filter(|location| {
-            match location.typ {
-                LocationType::Shrine => location.challenge.is_none(),
-                _ => false,
+        .filter(|location| location.map_point.is_none())
+        .filter(|location| !matches!(location.typ, LocationType::Region | LocationType::Shrine))
+        .map(|location| location.name.clone())
+        .collect();
+    map_match
+}
+
+// Loads the shipped nicknames and then the player's own aliases, if they have any. This has to
+// come after everything else is loaded since each alias is checked against the list it's in.
+pub fn load_aliases(model: &mut Model, source: &DataSource) -> Result<(), Vec<ParseError>> {
+    let mut errors = vec![];
+    if let Err(mut file_errors) = load_alias_file(model, source, FILE_NAME_ALIASES) {
+        errors.append(&mut file_errors);
+    }
+    match source.read_to_string(FILE_NAME_USER_ALIASES) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+        _ => {
+            if let Err(mut file_errors) = load_alias_file(model, source, FILE_NAME_USER_ALIASES) {
+                errors.append(&mut file_errors);
             }
-        }) {
+        }
+    }
+    errors_to_result(errors)
+}
 
-        dbg!(location);
+fn load_alias_file(model: &mut Model, source: &DataSource, file_name: &str) -> Result<(), Vec<ParseError>> {
+    let mut errors = vec![];
+    let mut alias_list = None;
+    for (line_number, line) in read_lines(source, file_name)? {
+        if line.starts_with(PREFIX_HEADER) {
+            alias_list = AliasList::from_section_name(&line.replace(PREFIX_HEADER, ""));
+            if alias_list.is_none() {
+                errors.push(ParseError::new(file_name, Some(line_number), &line, "a header such as \"#Locations\""));
+            }
+            continue;
+        }
+        let alias_list = match alias_list {
+            Some(alias_list) => alias_list,
+            None => {
+                errors.push(ParseError::new(file_name, Some(line_number), &line, "an alias under a valid \"#\" header"));
+                continue;
+            }
+        };
+        match parse::split_1_or_2(&line, ": ") {
+            (alias, Some(name)) => {
+                let key = name.trim().to_lowercase();
+                let found = match alias_list {
+                    AliasList::Character => model.characters.contains_key(&key),
+                    AliasList::Location => model.locations.contains_key(&key),
+                    AliasList::Quest => model.quests.contains_key(&key),
+                    AliasList::Item => model.items.contains_key(&key),
+                };
+                if found {
+                    model.aliases.add(alias_list, alias, name);
+                } else {
+                    errors.push(ParseError::new(file_name, Some(line_number), &line, &format!("a known {} after the colon", alias_list.variant_to_string().to_lowercase())));
+                }
+            },
+            _ => errors.push(ParseError::new(file_name, Some(line_number), &line, "\"alias: name\"")),
+        }
     }
-        //.count();
-    //bg!(&missing_challenge_count);
+    errors_to_result(errors)
 }
 
-pub fn load_quests(model: &mut Model) {
-    let file = File::open(FILE_NAME_QUESTS).unwrap();
-    let reader = BufReader::new(file);
+pub fn load_quests(model: &mut Model, source: &DataSource) -> Result<(), Vec<ParseError>> {
+    let file_name = FILE_NAME_QUESTS;
+    let mut errors = vec![];
     let mut quest_type_name = None;
-    for line in reader.lines()
-        .map(|line| line.unwrap().trim().to_string())
-        .filter(|line| !line.is_empty())
-        .filter(|line| !line.starts_with(PREFIX_COMMENT)) {
+    for (line_number, line) in read_lines(source, file_name)? {
 
         if line.starts_with(PREFIX_HEADER) {
             quest_type_name = Some(line.replace(PREFIX_HEADER, ""));
+            continue;
+        }
+        let (line, content_pack) = split_content_pack(&line);
+        if !model.content_options.includes(content_pack) {
+            continue;
+        }
+        let mut quest = match quest_type_name.as_deref() {
+            Some("Main") => {
+                Quest::new_main(&line)
+            },
+            Some("Side") => {
+                let (name, notes) = extract::extract_optional(&line, "(", ")");
+                let mut quest = Quest::new_side(name.trim(), None);
+                if let Some(notes) = notes {
+                    if let Err(error) = parse_quest_notes(model, &mut quest, &notes) {
+                        errors.push(ParseError::new(file_name, Some(line_number), &line, &error));
+                        continue;
+                    }
+                }
+                quest
+            },
+            Some("Shrine") => {
+                match parse::split_1_or_2(&line, ":") {
+                    (name, Some(shrine_name)) => {
+                        let (name, shrine_name) = (name.trim(), shrine_name.trim());
+                        //bg!(shrine_name);
+                        let shrine_id = model.locations.id(&shrine_name.to_lowercase());
+                        match shrine_id.map(|shrine_id| (shrine_id, model.locations.by_id(shrine_id))) {
+                            Some((_, location)) if location.quest.is_some() => {
+                                errors.push(ParseError::new(file_name, Some(line_number), &line, "a shrine that does not already have a quest"));
+                                continue;
+                            },
+                            Some((shrine_id, location)) if location.typ == LocationType::Shrine => {
+                                Quest::new_shrine(name, shrine_id)
+                            },
+                            _ => {
+                                errors.push(ParseError::new(file_name, Some(line_number), &line, "the name of a known shrine after the colon"));
+                                continue;
+                            }
+                        }
+                    },
+                    _ => {
+                        errors.push(ParseError::new(file_name, Some(line_number), &line, "\"quest: shrine\""));
+                        continue;
+                    }
+                }
+            },
+            _ => {
+                errors.push(ParseError::new(file_name, Some(line_number), &line, "a quest under a \"#Main\", \"#Side\", or \"#Shrine\" header"));
+                continue;
+            }
+        };
+        if model.quests.contains_key(&quest.name.to_lowercase()) {
+            errors.push(ParseError::new(file_name, Some(line_number), &line, "a quest name that is not already defined"));
         } else {
-            match quest_type_name.clone().unwrap().as_ref() {
-                "Main" => {
-                    model.add_quest(Quest::new_main(&line));
-                },
-                "Side" => {
-                    let (name, notes) = extract::extract_optional(&line, "(", ")");
-                    model.add_quest(Quest::new_side(name.trim(), notes.clone()));
-                },
-                "Shrine" => {
-                    let (name, shrine_name) = parse::split_2_trim(&line, ":");
-                    //bg!(shrine_name);
-                    model.get_shrine_mut(shrine_name).quest = Some(name.to_string());
-                    model.add_quest(Quest::new_shrine(name, shrine_name));
-                },
-                _ => panic!()
-            };
+            quest.content_pack = content_pack;
+            let shrine_id = quest.shrine;
+            let quest_id = model.add_quest(quest);
+            if let Some(shrine_id) = shrine_id {
+                model.locations.by_id_mut(shrine_id).quest = Some(quest_id);
+            }
+        }
+    }
+    errors_to_result(errors)
+}
+
+// Pulls the "giver:", "location:", and "region:" parts out of the notes for a side quest and
+// keeps the rest, such as "Requires Koko’s Kitchen", as plain notes. The names are checked against
+// the characters and locations that are already loaded. The returned error is the expected shape
+// of the line.
+fn parse_quest_notes(model: &Model, quest: &mut Quest, notes: &str) -> Result<(), String> {
+    let mut plain_notes = vec![];
+    let (mut giver, mut start_location, mut region) = (None, None, None);
+    for part in notes.split(SEPARATOR_QUEST_NOTES).map(|part| part.trim()).filter(|part| !part.is_empty()) {
+        match parse::split_1_or_2(part, ":") {
+            (QUEST_NOTE_GIVER, Some(name)) => giver = Some(name),
+            (QUEST_NOTE_LOCATION, Some(name)) => start_location = Some(name),
+            (QUEST_NOTE_REGION, Some(name)) => region = Some(name),
+            _ => plain_notes.push(part),
         }
     }
+    model.link_quest(quest, giver, start_location, region)?;
+    if !plain_notes.is_empty() {
+        quest.notes = Some(plain_notes.join(&format!("{} ", SEPARATOR_QUEST_NOTES)));
+    }
+    Ok(())
+}
+
+pub fn load_items(model: &mut Model, source: &DataSource) -> Result<(), Vec<ParseError>> {
+    let file_name = FILE_NAME_ITEMS;
+    let mut errors = vec![];
+    let mut section_name = None;
+    let mut item_type = None;
+    let mut recipe_type = None;
+    let mut purchase_item_name: Option<String> = None;
+    let mut armor_set: Option<ArmorSet> = None;
+    // Items from content packs that aren't included. The later sections may still mention them.
+    let mut excluded_item_names = BTreeSet::new();
+    // Set after an excluded purchase line or armor set so that the lines under it are skipped too.
+    let mut skip_details = false;
+    for (line_number, line) in read_lines(source, file_name)? {
+        if line.starts_with(PREFIX_HEADER) {
+            section_name = Some(line.replace(PREFIX_HEADER, ""));
+            item_type = None;
+            recipe_type = None;
+            purchase_item_name = None;
+            if let Some(armor_set) = armor_set.take() {
+                model.add_armor_set(armor_set);
+            }
+            skip_details = false;
+            continue;
+        }
+        let (line, content_pack) = split_content_pack(&line);
+        if !model.content_options.includes(content_pack) {
+            let (name, _) = parse::split_1_or_2(&line, SEPARATOR_COLUMN);
+            let (name, _) = parse::split_1_or_2(name, ": ");
+            excluded_item_names.insert(name.trim().to_lowercase());
+            // The upgrade lines that follow an excluded purchase line go with it, as do the pieces
+            // of an excluded armor set.
+            purchase_item_name = None;
+            if let Some(armor_set) = armor_set.take() {
+                model.add_armor_set(armor_set);
+            }
+            skip_details = true;
+            continue;
+        }
+        match section_name.as_deref() {
+            Some(SECTION_ITEMS_IN_ORDER) => {
+                if line.starts_with(PREFIX_SUBHEADER) {
+                    let subsection_name = line.replace(PREFIX_SUBHEADER, "");
+                    item_type = ItemType::from_subsection_name(&subsection_name);
+                    recipe_type = RecipeType::from_subsection_name(&subsection_name);
+                    if item_type.is_none() {
+                        errors.push(ParseError::new(file_name, Some(line_number), &line, "an item subsection such as \">Materials\""));
+                    }
+                    continue;
+                }
+                // The food subsections such as Dishes have the ingredients after the item name.
+                let (name, ingredients) = parse::split_1_or_2(&line, SEPARATOR_COLUMN);
+                let name = name.trim();
+                match item_type {
+                    Some(item_type) => {
+                        if model.items.contains_key(&name.to_lowercase()) {
+                            errors.push(ParseError::new(file_name, Some(line_number), &line, "an item name that is not already defined"));
+                        } else {
+                            let sequence = model.items.len();
+                            let mut item = Item::new(name, item_type, sequence);
+                            item.content_pack = content_pack;
+                            model.add_item(item);
+                        }
+                    },
+                    None => errors.push(ParseError::new(file_name, Some(line_number), &line, "an item under a valid \">\" subsection")),
+                }
+                if let Some(recipe_type) = recipe_type {
+                    match parse_recipe(model, name, recipe_type, ingredients) {
+                        Ok(mut recipe) => {
+                            recipe.content_pack = content_pack;
+                            model.add_recipe(recipe)
+                        },
+                        Err(error) => errors.push(ParseError::new(file_name, Some(line_number), &line, &error)),
+                    }
+                }
+            },
+            Some(SECTION_ITEM_EFFECTS) => {
+                match parse::split_1_or_2(&line, SEPARATOR_COLUMN) {
+                    (name, Some(description)) => {
+                        match model.items.get_mut(&name.trim().to_lowercase()) {
+                            Some(item) => {
+                                item.effect = Effect::from_description(description);
+                                item.effect_description = Some(description.trim().to_string());
+                                item.is_monster_part = description.contains(PHRASE_MONSTER_PART);
+                            },
+                            None if excluded_item_names.contains(&name.trim().to_lowercase()) => {},
+                            None => errors.push(ParseError::new(file_name, Some(line_number), &line, "the name of a known item before the tab")),
+                        }
+                    },
+                    _ => errors.push(ParseError::new(file_name, Some(line_number), &line, "\"item<tab>effect description\"")),
+                }
+            },
+            Some(SECTION_PURCHASE_AND_UPGRADE) => {
+                if is_upgrade_line(&line) {
+                    if skip_details {
+                        continue;
+                    }
+                } else {
+                    skip_details = false;
+                }
+                if let Err(error) = load_purchase_and_upgrade_line(model, &mut purchase_item_name, &line) {
+                    errors.push(ParseError::new(file_name, Some(line_number), &line, &error));
+                }
+            },
+            Some(SECTION_MON_SELL_PRICES) => {
+                match parse::split_1_or_2(&line, SEPARATOR_COLUMN) {
+                    (name, Some(mon_price)) => {
+                        let mon_price = match mon_price.trim().parse::<usize>() {
+                            Ok(mon_price) => mon_price,
+                            Err(_) => {
+                                errors.push(ParseError::new(file_name, Some(line_number), &line, "a whole number of Mon after the tab"));
+                                continue;
+                            }
+                        };
+                        // A name like "Dragon's Scale" has the same price for each of the dragons.
+                        let names = if name.contains(DRAGON_PLACEHOLDER) {
+                            DRAGON_NAMES.iter().map(|dragon| name.replace(DRAGON_PLACEHOLDER, dragon)).collect()
+                        } else {
+                            vec![name.to_string()]
+                        };
+                        for name in names.iter() {
+                            match model.items.get_mut(&name.trim().to_lowercase()) {
+                                Some(item) => item.mon_sell_price = Some(mon_price),
+                                None if excluded_item_names.contains(&name.trim().to_lowercase()) => {},
+                                None => errors.push(ParseError::new(file_name, Some(line_number), &line, "the name of a known item before the tab")),
+                            }
+                        }
+                    },
+                    _ => errors.push(ParseError::new(file_name, Some(line_number), &line, "\"item<tab>Mon price\"")),
+                }
+            },
+            Some(SECTION_ARMOR_SETS) => {
+                if line.starts_with(PREFIX_SUBHEADER) {
+                    if let Some(armor_set) = armor_set.take() {
+                        model.add_armor_set(armor_set);
+                    }
+                    skip_details = false;
+                    let header = line.replace(PREFIX_SUBHEADER, "");
+                    let (name, set_bonus) = parse::split_1_or_2(&header, ":");
+                    let name = name.trim().to_string();
+                    if model.armor_sets.contains_key(&name.to_lowercase()) {
+                        errors.push(ParseError::new(file_name, Some(line_number), &line, "an armor set name that is not already defined"));
+                        continue;
+                    }
+                    let mut new_armor_set = ArmorSet::new(&name, set_bonus.map(|set_bonus| set_bonus.trim().to_string()));
+                    new_armor_set.content_pack = content_pack;
+                    armor_set = Some(new_armor_set);
+                    continue;
+                }
+                if skip_details {
+                    continue;
+                }
+                match armor_set.as_mut() {
+                    Some(armor_set) => {
+                        if let Err(error) = load_armor_set_line(model, armor_set, &excluded_item_names, &line) {
+                            errors.push(ParseError::new(file_name, Some(line_number), &line, &error));
+                        }
+                    },
+                    None => errors.push(ParseError::new(file_name, Some(line_number), &line, "a line under a \">\" armor set subheader")),
+                }
+            },
+            _ => errors.push(ParseError::new(file_name, Some(line_number), &line, "a line under a known \"#\" section header")),
+        }
+    }
+    if let Some(armor_set) = armor_set.take() {
+        model.add_armor_set(armor_set);
+    }
+    errors_to_result(errors)
+}
+
+// A line under an armor set is a piece such as "head: Hylian Hood" or a shop that sells the set
+// such as "shop: Rito Village". A set can have more than one shop. The returned error is the
+// expected shape of the line.
+fn load_armor_set_line(model: &Model, armor_set: &mut ArmorSet, excluded_item_names: &BTreeSet<String>, line: &str) -> Result<(), String> {
+    let (label, name) = match parse::split_1_or_2(line, ":") {
+        (label, Some(name)) => (label.trim(), name.trim()),
+        _ => return Err("\"head\", \"body\", \"legs\", or \"shop\" followed by \": \" and a name".to_string()),
+    };
+    if label == ARMOR_SET_SHOP {
+        return match model.locations.id(&name.to_lowercase()) {
+            Some(location_id) if armor_set.shop_locations.contains(&location_id) => Err("a shop that is not already listed for the set".to_string()),
+            Some(location_id) => {
+                armor_set.shop_locations.push(location_id);
+                Ok(())
+            },
+            None => Err("the name of a known location after \"shop: \"".to_string()),
+        };
+    }
+    let slot = ArmorSlot::from_label(label).ok_or_else(|| "\"head\", \"body\", \"legs\", or \"shop\" before the \":\"".to_string())?;
+    if armor_set.get_piece(slot).is_some() {
+        return Err(format!("only one {} piece in the set", slot.variant_to_string().to_lowercase()));
+    }
+    match model.items.id(&name.to_lowercase()) {
+        Some(item_id) if model.items.by_id(item_id).typ == ItemType::Armor => {
+            armor_set.set_piece(slot, item_id);
+            Ok(())
+        },
+        Some(_) => Err("the name of a piece of armor".to_string()),
+        None if excluded_item_names.contains(&name.to_lowercase()) => Ok(()),
+        None => Err("the name of a known item".to_string()),
+    }
+}
+
+fn is_upgrade_line(line: &str) -> bool {
+    parse::split_1_or_2(line, ":").0.trim().parse::<usize>().is_ok()
+}
+
+// A purchase line such as "Amber Earrings: 100 Rupee, 10 Amber" is followed by up to four upgrade
+// lines such as "1: 5 Amber, 3 Flint". The purchase price is optional since some items can only be
+// upgraded. The returned error is the expected shape of the line.
+fn load_purchase_and_upgrade_line(model: &mut Model, purchase_item_name: &mut Option<String>, line: &str) -> Result<(), String> {
+    if is_upgrade_line(line) {
+        let (level, rest) = parse::split_1_or_2(line, ":");
+        let level = level.trim().parse::<usize>().unwrap();
+        let price = parse_price(model, rest.unwrap_or(""))?;
+        let item_name = purchase_item_name.as_ref().ok_or("an upgrade level under a purchased item")?;
+        let item = model.get_item_mut(item_name);
+        if level != item.upgrade_prices.len() + 1 || level > UPGRADE_LEVEL_COUNT {
+            return Err(format!("upgrade level {} for {}", item.upgrade_prices.len() + 1, item_name));
+        }
+        item.upgrade_prices.push(price);
+    } else {
+        // Split on ": " rather than ":" so that an item name like "Medal of Honor: Talus" isn't
+        // broken up.
+        let (item_name, purchase_price) = parse::split_1_or_2(line, ": ");
+        let item_name = item_name.trim();
+        // An item listed without a price gets an empty one so that it's written back the same way.
+        let price = parse_price(model, purchase_price.unwrap_or(""))?;
+        let item = model.items.get_mut(&item_name.to_lowercase()).ok_or("the name of a known item")?;
+        if item.purchase_price.is_some() || !item.upgrade_prices.is_empty() {
+            return Err("an item that is not already listed".to_string());
+        }
+        item.purchase_price = Some(price);
+        *purchase_item_name = Some(item.name.clone());
+    }
+    Ok(())
+}
+
+// The ingredients look like "Apple, Wildberry, Hydromelon" for dishes and like
+// "Razorclaw Crab/Ironshell Crab set on fire." for roasted and frozen foods, where a slash
+// separates alternatives. Elixirs have a level in the name such as "Lv. 2 Hasty Elixir" and no
+// ingredients. The returned error is the expected shape of the line.
+fn parse_recipe(model: &Model, name: &str, recipe_type: RecipeType, ingredients: Option<&str>) -> Result<Recipe, String> {
+    let mut recipe = Recipe::new(name, recipe_type);
+    if let Some(level_and_name) = name.strip_prefix(PREFIX_RECIPE_LEVEL) {
+        let (level, _) = parse::split_1_or_2(level_and_name, " ");
+        recipe.level = Some(level.parse::<usize>().map_err(|_| "a recipe level such as \"Lv. 2\"")?);
+    }
+    if let Some(ingredients) = ingredients {
+        let mut ingredients = ingredients.trim();
+        if let Some(method) = RECIPE_METHODS.iter().find(|method| ingredients.ends_with(*method)) {
+            ingredients = ingredients.trim_end_matches(method);
+            recipe.method = Some(method.to_string());
+        }
+        for slot in ingredients.split(",").map(|slot| slot.trim()).filter(|slot| !slot.is_empty()) {
+            let alternatives = slot.split("/").map(|alternative| alternative.trim().to_string()).collect::<Vec<_>>();
+            for alternative in alternatives.iter() {
+                if !model.items.contains_key(&alternative.to_lowercase()) {
+                    return Err(format!("a known item as the ingredient \"{}\"", alternative));
+                }
+            }
+            recipe.ingredients.push(Ingredient::new(alternatives));
+        }
+    }
+    Ok(recipe)
+}
+
+fn parse_price(model: &Model, s: &str) -> Result<Price, String> {
+    let mut price = Price::default();
+    for part in s.split(",").map(|part| part.trim()).filter(|part| !part.is_empty()) {
+        let (first, rest) = parse::split_1_or_2(part, " ");
+        let component = match (first.parse::<usize>(), rest) {
+            (Ok(quantity), Some(name)) => {
+                let name = name.trim();
+                if !model.items.contains_key(&name.to_lowercase()) {
+                    return Err(format!("a known item in \"{}\"", part));
+                }
+                PriceComponent::Item { name: name.to_string(), quantity }
+            },
+            _ => PriceComponent::Requirement { name: part.to_string() },
+        };
+        price.components.push(component);
+    }
+    Ok(price)
 }
 
 /*
@@ -308,4 +967,107 @@ pub fn load_locations(model: &mut Model) {
 fn add_location(model: &mut Model, name: &str, typ: LocationType, region: Region) {
     model.add_location(Location::new(name, &region, typ));
 }
-*/
\ No newline at end of file
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::catalog::Catalog;
+    use super::super::data_source;
+
+    #[test]
+    fn errors_from_all_files_are_collected() {
+        let path = data_source::copy_embedded_files_to_temp("parse_errors");
+        let append = |file_name: &str, text: &str| {
+            let content = fs::read_to_string(path.join(file_name)).unwrap();
+            fs::write(path.join(file_name), format!("{}\n{}\n", content, text)).unwrap();
+        };
+        append(FILE_NAME_CHARACTERS, "#Not A Race\nSomeone");
+        append(FILE_NAME_CHARACTERS, "#Hylian\nImpa");
+        append(FILE_NAME_ALIASES, "no colon here");
+
+        let errors = Model::load_text(&DataSource::new_directory(&path), &ContentOptions::default()).unwrap_err();
+        let lines = errors.iter().map(|error| (error.file_name.as_str(), error.line.as_str())).collect::<Vec<_>>();
+        assert!(lines.contains(&(FILE_NAME_CHARACTERS, "#Not A Race")));
+        assert!(lines.contains(&(FILE_NAME_CHARACTERS, "Impa")));
+        assert!(lines.contains(&(FILE_NAME_ALIASES, "no colon here")));
+        // The character under the bad header isn't reported separately.
+        assert!(!lines.contains(&(FILE_NAME_CHARACTERS, "Someone")));
+        assert!(errors.iter().all(|error| error.line_number.is_some()));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn base_game_leaves_out_dlc() {
+        let source = DataSource::new_embedded();
+        let model = Model::load_text(&source, &ContentOptions::new_base_game()).unwrap();
+        assert!(model.quests.values().all(|quest| quest.content_pack == ContentPack::Base && !quest.name.starts_with("EX ")));
+        assert!(model.items.values().all(|item| item.content_pack == ContentPack::Base));
+        assert!(model.locations.values().all(|location| location.content_pack == ContentPack::Base));
+        assert!(model.characters.values().all(|character| character.content_pack == ContentPack::Base));
+
+        let model = Model::load_text(&source, &ContentOptions::default()).unwrap();
+        let ex_quests = model.quests.values().filter(|quest| quest.name.starts_with("EX ")).collect::<Vec<_>>();
+        assert_eq!(13, ex_quests.len());
+        assert_eq!(3, ex_quests.iter().filter(|quest| quest.content_pack == ContentPack::Dlc2).count());
+        assert!(ex_quests.iter().all(|quest| quest.content_pack != ContentPack::Base));
+    }
+
+    #[test]
+    fn quest_notes_link_the_giver_and_region() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        let quest = model.get_quest("The Thunder Helm");
+        assert_eq!(quest.giver.map(|giver_id| model.character_name(giver_id)), Some("Riju"));
+        assert_eq!(quest.start_location.map(|location_id| model.location_name(location_id)), Some("Gerudo Town"));
+        assert_eq!(quest.region.map(|region_id| model.location_name(region_id)), Some("Gerudo Wasteland"));
+        assert_eq!(model.get_quest("A Wife Washed Away").region.map(|region_id| model.location_name(region_id)), Some("Lanayru"));
+
+        let mut quest = Quest::new_side("Test", None);
+        assert!(parse_quest_notes(&model, &mut quest, "Requires nothing; location: Kakariko Village; region: Hebra").is_err());
+        let mut quest = Quest::new_side("Test", None);
+        assert!(parse_quest_notes(&model, &mut quest, "giver: Nobody").is_err());
+        let mut quest = Quest::new_side("Test", None);
+        parse_quest_notes(&model, &mut quest, "Requires nothing; location: Kakariko Village; region: Dueling Peaks").unwrap();
+        assert_eq!(quest.notes.as_deref(), Some("Requires nothing"));
+    }
+
+    #[test]
+    fn locations_under_an_excluded_region_are_left_out() {
+        let path = data_source::copy_embedded_files_to_temp("parse_excluded_region");
+        let content = fs::read_to_string(path.join(FILE_NAME_LOCATIONS)).unwrap();
+        fs::write(path.join(FILE_NAME_LOCATIONS), format!("{}\n#Test Region (DLC2)\n>Test Area (DLC1)\nTest Spot\n", content)).unwrap();
+        let source = DataSource::new_directory(&path);
+        let content_options = ContentOptions { dlc_2: false, ..ContentOptions::new_all() };
+        let check = |model: &Model| {
+            assert!(!model.locations.contains_key("test area"));
+            assert!(!model.locations.contains_key("test spot"));
+            assert!(model.root_locations().iter().all(|id| model.locations.by_id(*id).typ == LocationType::Region));
+        };
+        check(&Model::load_text(&source, &content_options).unwrap());
+        let catalog = Catalog::from_model(&Model::load_text(&source, &ContentOptions::new_all()).unwrap());
+        check(&catalog.to_model(&content_options).unwrap());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn every_map_entry_is_matched() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::new_all()).unwrap();
+        let map_match = model.map_location_match.as_ref().unwrap();
+        assert!(map_match.unmatched_map_names.is_empty(), "{:?}", map_match.unmatched_map_names);
+        assert!(map_match.duplicate_map_names.is_empty(), "{:?}", map_match.duplicate_map_names);
+        let fountain = model.get_location("Akkala Great Fairy Fountain");
+        assert_eq!(fountain.internal_name.as_deref(), Some("Location_WeaponCureSpring_02"));
+        assert!(fountain.map_point.is_some());
+        let shop = model.get_location("Ore and More");
+        assert_eq!(shop.internal_name.as_deref(), Some("Location_UMiiVillageShopJewel"));
+        assert_eq!(model.locations.values().filter(|location| location.internal_name.is_some()).count(), model.map_entries.len());
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        let path = data_source::copy_embedded_files_to_temp("parse_missing_file");
+        fs::remove_file(path.join(FILE_NAME_QUESTS)).unwrap();
+        let errors = Model::load_text(&DataSource::new_directory(&path), &ContentOptions::default()).unwrap_err();
+        assert!(errors.iter().any(|error| error.file_name == FILE_NAME_QUESTS && error.line_number.is_none()));
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
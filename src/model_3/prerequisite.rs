@@ -0,0 +1,218 @@
+use super::game_record::{GameEvent, GameEventType};
+use super::lookup::LookupError;
+use super::model::*;
+
+// Which events have to have happened before another one can. Each rule says that an event of one
+// type needs an event of another type on a related target, like starting a shrine needing the
+// shrine's quest to be completed. Following the rules from an event gives everything it implies,
+// so completing an unvisited shrine also discovers it and finishes its quest.
+//
+// The rules only ever point from a location to its parent, from a shrine or quest to something
+// it was listed with, from an armor level to a lower one, or from a Great Fairy to her fountain,
+// so following them always comes to an end.
+//
+// A Great Fairy counts as freed once she's been met, and each one freed allows one more level of
+// armor upgrades. Which fairies it takes doesn't matter, so upgrading past the number already
+// freed brings in the ones not yet met, in name order.
+
+const GREAT_FAIRY_FOUNTAINS: [(&str, &str); 4] = [
+    ("Cotera", "Dueling Peaks Great Fairy Fountain"),
+    ("Kaysa", "Akkala Great Fairy Fountain"),
+    ("Mija", "Tabantha Great Fairy Fountain"),
+    ("Tera", "Gerudo Wasteland Great Fairy Fountain"),
+];
+
+#[derive(Clone, Debug)]
+pub struct Prerequisite {
+    pub typ: GameEventType,
+    pub target: RelatedTarget,
+}
+
+// How to get from an event's target to the target of the event it needs.
+#[derive(Clone, Debug)]
+pub enum RelatedTarget {
+    Same,
+    ParentLocation,
+    // The quest that unlocks a shrine, if it has one.
+    ShrineQuest,
+    // The town or other place where a quest is picked up, if the catalog says.
+    QuestStartLocation,
+    // The same piece of armor one level down, for levels above 1.
+    PreviousArmorLevel,
+    // A Great Fairy who has to be freed to allow the armor level.
+    GreatFairyForArmorLevel,
+    // The fountain of a Great Fairy. Other characters don't have one.
+    GreatFairyFountain,
+}
+
+impl GameEventType {
+    pub fn prerequisites(&self) -> Vec<Prerequisite> {
+        let rule = |typ, target| Prerequisite { typ, target };
+        match self {
+            GameEventType::ActivateTower => vec![rule(GameEventType::DiscoverLocation, RelatedTarget::Same)],
+            GameEventType::CharacterDeath => vec![rule(GameEventType::MeetCharacter, RelatedTarget::Same)],
+            GameEventType::CompleteQuest => vec![rule(GameEventType::StartQuest, RelatedTarget::Same)],
+            GameEventType::CompleteShrine => vec![rule(GameEventType::StartShrine, RelatedTarget::Same)],
+            GameEventType::DiscoverLocation => vec![rule(GameEventType::DiscoverLocation, RelatedTarget::ParentLocation)],
+            GameEventType::FindDogTreasure => vec![rule(GameEventType::DiscoverLocation, RelatedTarget::Same)],
+            GameEventType::LightFlame => vec![rule(GameEventType::DiscoverLocation, RelatedTarget::Same)],
+            GameEventType::MeetCharacter => vec![rule(GameEventType::DiscoverLocation, RelatedTarget::GreatFairyFountain)],
+            GameEventType::SetArmorLevel => vec![
+                rule(GameEventType::IdentifyItem, RelatedTarget::Same),
+                rule(GameEventType::SetArmorLevel, RelatedTarget::PreviousArmorLevel),
+                rule(GameEventType::MeetCharacter, RelatedTarget::GreatFairyForArmorLevel),
+            ],
+            GameEventType::SetItemCount => vec![rule(GameEventType::IdentifyItem, RelatedTarget::Same)],
+            GameEventType::StartQuest => vec![rule(GameEventType::DiscoverLocation, RelatedTarget::QuestStartLocation)],
+            GameEventType::StartShrine => vec![
+                rule(GameEventType::DiscoverLocation, RelatedTarget::Same),
+                rule(GameEventType::CompleteQuest, RelatedTarget::ShrineQuest),
+            ],
+            _ => vec![],
+        }
+    }
+}
+
+impl RelatedTarget {
+    // The name of the related target and the number for the event on it, or None if there isn't
+    // one, like the parent of a region.
+    fn resolve(&self, model: &Model, event: &GameEvent) -> Result<Option<(String, Option<usize>)>, LookupError> {
+        let name = event.name();
+        Ok(match self {
+            RelatedTarget::Same => Some((name.to_string(), None)),
+            RelatedTarget::ParentLocation => model.try_get_parent_location(name)?.map(|location| (location.name.clone(), None)),
+            RelatedTarget::ShrineQuest => model.try_get_shrine(name)?.quest.map(|quest_id| (model.quest_name(quest_id).to_string(), None)),
+            RelatedTarget::QuestStartLocation => model.try_get_quest(name)?.start_location.map(|location_id| (model.location_name(location_id).to_string(), None)),
+            RelatedTarget::PreviousArmorLevel => {
+                let armor = model.try_get_armor(name)?;
+                match event.number() {
+                    Some(level) if level > 1 => Some((armor.name.clone(), Some(level - 1))),
+                    _ => None,
+                }
+            },
+            RelatedTarget::GreatFairyForArmorLevel => {
+                let level = event.number().unwrap_or(0);
+                let (freed, not_freed): (Vec<&Character>, Vec<&Character>) = model.characters
+                    .values()
+                    .filter(|character| character.race == Race::GreatFairy)
+                    .partition(|character| character.is_met());
+                if level <= freed.len() {
+                    None
+                } else {
+                    not_freed.get(level - freed.len() - 1).map(|character| (character.name.clone(), None))
+                }
+            },
+            RelatedTarget::GreatFairyFountain => {
+                let character = model.try_get_character(name)?;
+                GREAT_FAIRY_FOUNTAINS
+                    .iter()
+                    .find(|(fairy_name, _)| *fairy_name == character.name)
+                    .map(|(_, fountain_name)| (fountain_name.to_string(), None))
+            },
+        })
+    }
+}
+
+impl GameEvent {
+    // The events this one directly needs that haven't happened yet, at the same time as this one.
+    pub fn missing_prerequisites(&self, model: &Model) -> Result<Vec<GameEvent>, LookupError> {
+        let mut events = vec![];
+        for prerequisite in self.typ().prerequisites() {
+            if let Some((name, number)) = prerequisite.target.resolve(model, self)? {
+                let event = GameEvent::new_predecessor(self.time(), prerequisite.typ, &name, number);
+                if !event.has_happened(model)? {
+                    events.push(event);
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    // This event along with everything it needs that hasn't happened yet, each event once and
+    // after all of the events it needs.
+    pub fn expand(&self, model: &Model) -> Result<Vec<GameEvent>, LookupError> {
+        let mut events = vec![];
+        self.expand_into(model, &mut events)?;
+        Ok(events)
+    }
+
+    fn expand_into(&self, model: &Model, events: &mut Vec<GameEvent>) -> Result<(), LookupError> {
+        for event in self.missing_prerequisites(model)? {
+            if !events.iter().any(|other| other.is_same_as(&event)) {
+                event.expand_into(model, events)?;
+            }
+        }
+        events.push(self.clone());
+        Ok(())
+    }
+
+    fn is_same_as(&self, other: &GameEvent) -> bool {
+        self.typ().variant_to_string() == other.typ().variant_to_string()
+            && self.name().to_lowercase() == other.name().to_lowercase()
+            && self.number() == other.number()
+    }
+
+    // Whether the model already has what the event would do. Events like KorokSeed and
+    // SetItemCount can happen any number of times so they never have.
+    pub fn has_happened(&self, model: &Model) -> Result<bool, LookupError> {
+        let name = self.name();
+        Ok(match self.typ() {
+            GameEventType::ActivateTower => model.try_get_tower(name)?.is_activated(),
+            GameEventType::AddToCompendium => model.try_get_item(name)?.is_in_compendium(),
+            GameEventType::CharacterDeath => !model.try_get_character(name)?.is_alive(),
+            GameEventType::CompleteQuest => model.try_get_quest(name)?.is_completed(),
+            GameEventType::CompleteShrine => model.try_get_shrine(name)?.is_completed(),
+            GameEventType::DiscoverLocation => model.try_get_location(name)?.is_discovered(),
+            GameEventType::FindDogTreasure => model.try_get_location(name)?.is_dog_treasure_found(),
+            GameEventType::IdentifyItem => model.try_get_item(name)?.is_identified(),
+            GameEventType::LightFlame => model.try_get_location(name)?.is_flame_lit(),
+            GameEventType::MeetCharacter => model.try_get_character(name)?.is_met(),
+            GameEventType::MeetCharacterFlashback => model.try_get_character(name)?.is_met_in_flashback(),
+            GameEventType::MentionCharacter => model.try_get_character(name)?.is_mentioned(),
+            GameEventType::SetArmorLevel => model.try_get_armor(name)?.armor_level >= self.number().unwrap_or(0),
+            GameEventType::StartQuest => model.try_get_quest(name)?.is_started(),
+            GameEventType::StartShrine => model.try_get_shrine(name)?.is_started(),
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::data_source::DataSource;
+
+    // Applies the expanded events in order, checking that each one's prerequisites have already
+    // happened by the time it's applied, and returns them.
+    fn expand_and_apply(model: &mut Model, event: GameEvent) -> Vec<GameEvent> {
+        let mut events = event.expand(model).unwrap();
+        for (index, event) in events.iter().enumerate() {
+            assert!(!events[index + 1..].iter().any(|other| other.is_same_as(event)), "{} is in the list twice.", event);
+        }
+        for event in events.iter_mut() {
+            assert!(event.missing_prerequisites(model).unwrap().is_empty(), "{} comes before what it needs.", event);
+            event.apply(model).unwrap();
+        }
+        events
+    }
+
+    #[test]
+    fn expanded_events_come_after_what_they_need_and_only_once() {
+        let mut model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        let events = expand_and_apply(&mut model, GameEvent::new(10, GameEventType::CompleteShrine, "Mezza Lo Shrine", None));
+        assert!(events.len() > 3);
+        assert!(events[..events.len() - 1].iter().all(GameEvent::is_predecessor));
+        assert!(model.get_shrine("Mezza Lo Shrine").is_completed());
+
+        // Each level of the armor needs the item identified, which should only be added once.
+        let events = expand_and_apply(&mut model, GameEvent::new(20, GameEventType::SetArmorLevel, "Hylian Hood", Some(3)));
+        let count = |typ: &str| events.iter().filter(|event| event.typ().variant_to_string() == typ).count();
+        assert_eq!(1, count("IdentifyItem"));
+        assert_eq!(3, count("SetArmorLevel"));
+        assert_eq!(3, count("MeetCharacter"));
+        assert_eq!(3, model.get_item("Hylian Hood").armor_level);
+
+        // Nothing is left to add once everything has happened.
+        assert_eq!(1, GameEvent::new(30, GameEventType::CompleteShrine, "Mezza Lo Shrine", None).expand(&model).unwrap().len());
+    }
+}
@@ -0,0 +1,340 @@
+use std::cmp::Ordering;
+
+use crate::*;
+use super::alias::AliasList;
+use super::arena::LocationId;
+use super::game_record::NULL_TIME;
+use super::lookup::LookupError;
+use super::model::*;
+
+// A filter over one of the model's lists that's built up a step at a time, as in:
+//   model.query_locations().within("Hebra")?.typ(LocationType::Shrine).status(Status::Undiscovered).has_quest(true).run()
+// Each step narrows the results. Steps that take a name, like within(), look it up right away and
+// return an error for an unknown name, so running a query can't fail.
+pub struct Query<'a, T: Queryable> {
+    model: &'a Model,
+    filters: Vec<QueryFilter<'a, T>>,
+    order: Option<QueryOrder<'a, T>>,
+}
+
+type QueryFilter<'a, T> = Box<dyn Fn(&Model, &T) -> bool + 'a>;
+type QueryOrder<'a, T> = Box<dyn Fn(&T, &T) -> Ordering + 'a>;
+
+// Progress that an entry can be filtered on. A status that doesn't apply to a kind of entry, like
+// Discovered for a quest, matches nothing.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Status {
+    Discovered,
+    Undiscovered,
+    Started,
+    NotStarted,
+    Completed,
+    Incomplete,
+    Mentioned,
+    Met,
+    NotMet,
+}
+
+// What the generic parts of a query need from the entries in a list.
+pub trait Queryable: Sized {
+    const ALIAS_LIST: AliasList;
+
+    fn all(model: &Model) -> Vec<&Self>;
+    fn name(&self) -> &str;
+    fn content_pack(&self) -> ContentPack;
+    // The times of everything that has happened to the entry, leaving out what hasn't happened.
+    fn event_times(&self) -> Vec<usize>;
+    fn has_status(&self, status: Status) -> bool;
+
+    // Whether the entry should come up for the partial name typed into the shell.
+    fn matches_text(&self, partial_name: &str) -> bool {
+        self.name().to_lowercase().contains(partial_name)
+    }
+}
+
+impl<'a, T: 'a + Queryable> Query<'a, T> {
+    pub fn new(model: &'a Model) -> Self {
+        Self {
+            model,
+            filters: vec![],
+            order: None,
+        }
+    }
+
+    pub fn filter(mut self, filter: impl Fn(&Model, &T) -> bool + 'a) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    // The partial name is matched against the names and aliases, ignoring case.
+    pub fn matching(self, partial_name: &str) -> Self {
+        let partial_name = partial_name.trim().to_lowercase();
+        let alias_keys = self.model.aliases.keys_containing(T::ALIAS_LIST, &partial_name);
+        self.filter(move |_, entry| entry.matches_text(&partial_name) || alias_keys.contains(&entry.name().to_lowercase()))
+    }
+
+    pub fn content_pack(self, content_pack: ContentPack) -> Self {
+        self.filter(move |_, entry| entry.content_pack() == content_pack)
+    }
+
+    pub fn status(self, status: Status) -> Self {
+        self.filter(move |_, entry| entry.has_status(status))
+    }
+
+    // Entries with something that happened from the start time up to but not including the end
+    // time.
+    pub fn between(self, start_time: usize, end_time: usize) -> Self {
+        self.filter(move |_, entry| entry.event_times().iter().any(|time| *time >= start_time && *time < end_time))
+    }
+
+    // Without an order the results are sorted by name.
+    pub fn sort_by(mut self, compare: impl Fn(&T, &T) -> Ordering + 'a) -> Self {
+        self.order = Some(Box::new(compare));
+        self
+    }
+
+    // Sorts by the time of the most recent thing that happened to each entry, most recent first.
+    pub fn sort_by_latest_time(self) -> Self {
+        self.sort_by(|a, b| latest_time(b).cmp(&latest_time(a)))
+    }
+
+    fn with_location(self, name: &str, filter: impl Fn(&Model, &T, LocationId) -> bool + 'a) -> Result<Self, LookupError> {
+        let location_id = self.model.try_location_id(name)?;
+        Ok(self.filter(move |model, entry| filter(model, entry, location_id)))
+    }
+
+    pub fn run(&self) -> Vec<&'a T> {
+        let mut results = T::all(self.model)
+            .into_iter()
+            .filter(|entry| self.filters.iter().all(|filter| filter(self.model, entry)))
+            .collect::<Vec<_>>();
+        if let Some(order) = &self.order {
+            results.sort_by(|a, b| order(a, b));
+        }
+        results
+    }
+
+    pub fn count(&self) -> usize {
+        self.run().len()
+    }
+
+    // The results split up by a key such as the location type or region, each group in the same
+    // order as run() would give.
+    pub fn group_by<K: Ord>(&self, key: impl Fn(&T) -> K) -> BTreeMap<K, Vec<&'a T>> {
+        let mut groups: BTreeMap<K, Vec<&'a T>> = BTreeMap::new();
+        for entry in self.run() {
+            groups.entry(key(entry)).or_default().push(entry);
+        }
+        groups
+    }
+}
+
+impl<'a> Query<'a, Location> {
+    pub fn typ(self, typ: LocationType) -> Self {
+        self.filter(move |_, location| location.typ == typ)
+    }
+
+    // Locations anywhere under the region or area, not counting the region or area itself.
+    pub fn within(self, name: &str) -> Result<Self, LookupError> {
+        self.with_location(name, |model, location, ancestor_id| {
+            location.parent_location.is_some_and(|parent_id| model.location_is_within(parent_id, ancestor_id))
+        })
+    }
+
+    pub fn has_quest(self, has_quest: bool) -> Self {
+        self.filter(move |_, location| location.quest.is_some() == has_quest)
+    }
+
+    pub fn has_dog_treasure(self, has_dog_treasure: bool) -> Self {
+        self.filter(move |_, location| location.has_dog_treasure() == has_dog_treasure)
+    }
+}
+
+impl<'a> Query<'a, Quest> {
+    pub fn typ(self, typ: QuestType) -> Self {
+        self.filter(move |_, quest| quest.typ == typ)
+    }
+
+    // Quests that start in the region or area, or for a shrine quest, whose shrine is in it.
+    pub fn within(self, name: &str) -> Result<Self, LookupError> {
+        self.with_location(name, |model, quest, ancestor_id| {
+            quest.start_location.or(quest.shrine).or(quest.region).is_some_and(|location_id| model.location_is_within(location_id, ancestor_id))
+        })
+    }
+
+    pub fn given_by(self, character_name: &str) -> Result<Self, LookupError> {
+        let character_id = self.model.try_character_id(character_name)?;
+        Ok(self.filter(move |_, quest| quest.giver == Some(character_id)))
+    }
+}
+
+impl<'a> Query<'a, Character> {
+    pub fn race(self, race: Race) -> Self {
+        self.filter(move |_, character| character.race == race)
+    }
+
+    pub fn alive(self, alive: bool) -> Self {
+        self.filter(move |_, character| character.is_alive() == alive)
+    }
+}
+
+impl<'a> Query<'a, Item> {
+    pub fn typ(self, typ: ItemType) -> Self {
+        self.filter(move |_, item| item.typ == typ)
+    }
+}
+
+fn latest_time<T: Queryable>(entry: &T) -> Option<usize> {
+    entry.event_times().into_iter().max()
+}
+
+fn happened(times: &[usize]) -> Vec<usize> {
+    times.iter().copied().filter(|time| *time != NULL_TIME).collect()
+}
+
+impl Queryable for Location {
+    const ALIAS_LIST: AliasList = AliasList::Location;
+
+    fn all(model: &Model) -> Vec<&Self> {
+        model.locations.values().collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn content_pack(&self) -> ContentPack {
+        self.content_pack
+    }
+
+    fn event_times(&self) -> Vec<usize> {
+        happened(&[self.discovered_time, self.dog_treasure_found_time, self.started_time, self.completed_time, self.flame_lit_time, self.activated_time])
+    }
+
+    // Only shrines can be started or completed.
+    fn has_status(&self, status: Status) -> bool {
+        let is_shrine = self.typ == LocationType::Shrine;
+        match status {
+            Status::Discovered => self.is_discovered(),
+            Status::Undiscovered => !self.is_discovered(),
+            Status::Started => is_shrine && self.is_started(),
+            Status::NotStarted => is_shrine && !self.is_started(),
+            Status::Completed => is_shrine && self.is_completed(),
+            Status::Incomplete => is_shrine && !self.is_completed(),
+            Status::Mentioned | Status::Met | Status::NotMet => false,
+        }
+    }
+
+    // A shrine also comes up for its challenge, as in "blessing".
+    fn matches_text(&self, partial_name: &str) -> bool {
+        self.name.to_lowercase().contains(partial_name)
+            || self.challenge.as_ref().is_some_and(|challenge| challenge.to_lowercase().contains(partial_name))
+    }
+}
+
+impl Queryable for Quest {
+    const ALIAS_LIST: AliasList = AliasList::Quest;
+
+    fn all(model: &Model) -> Vec<&Self> {
+        model.quests.values().collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn content_pack(&self) -> ContentPack {
+        self.content_pack
+    }
+
+    fn event_times(&self) -> Vec<usize> {
+        happened(&[self.started_time, self.completed_time])
+    }
+
+    fn has_status(&self, status: Status) -> bool {
+        match status {
+            Status::Started => self.is_started(),
+            Status::NotStarted => !self.is_started(),
+            Status::Completed => self.is_completed(),
+            Status::Incomplete => !self.is_completed(),
+            Status::Discovered | Status::Undiscovered | Status::Mentioned | Status::Met | Status::NotMet => false,
+        }
+    }
+}
+
+impl Queryable for Character {
+    const ALIAS_LIST: AliasList = AliasList::Character;
+
+    fn all(model: &Model) -> Vec<&Self> {
+        model.characters.values().collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn content_pack(&self) -> ContentPack {
+        self.content_pack
+    }
+
+    fn event_times(&self) -> Vec<usize> {
+        happened(&[self.mentioned_time, self.met_time, self.met_in_flashback_time, self.died_time])
+    }
+
+    fn has_status(&self, status: Status) -> bool {
+        match status {
+            Status::Mentioned => self.is_mentioned(),
+            Status::Met => self.is_met(),
+            Status::NotMet => !self.is_met(),
+            Status::Discovered | Status::Undiscovered | Status::Started | Status::NotStarted | Status::Completed | Status::Incomplete => false,
+        }
+    }
+}
+
+impl Queryable for Item {
+    const ALIAS_LIST: AliasList = AliasList::Item;
+
+    fn all(model: &Model) -> Vec<&Self> {
+        model.items.values().collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn content_pack(&self) -> ContentPack {
+        self.content_pack
+    }
+
+    fn event_times(&self) -> Vec<usize> {
+        happened(&[self.added_to_compendium_time, self.identified_time])
+    }
+
+    // An item counts as discovered once it's been identified.
+    fn has_status(&self, status: Status) -> bool {
+        match status {
+            Status::Discovered => self.is_identified(),
+            Status::Undiscovered => !self.is_identified(),
+            Status::Started | Status::NotStarted | Status::Completed | Status::Incomplete | Status::Mentioned | Status::Met | Status::NotMet => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::data_source::DataSource;
+
+    #[test]
+    fn named_filters_are_checked_when_added() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap();
+        assert!(matches!(model.query_locations().within("Hebraa"), Err(LookupError::Unknown { .. })));
+        assert!(model.query_quests().given_by("Nobody").is_err());
+
+        let hebra_id = model.location_id("Hebra").unwrap();
+        let shrines = model.query_locations().within("Hebra").unwrap().typ(LocationType::Shrine).run();
+        assert!(!shrines.is_empty());
+        assert!(shrines.iter().all(|shrine| model.location_is_within(model.location_id(&shrine.name).unwrap(), hebra_id)));
+        assert_eq!(shrines.len(), model.query_locations().within("Hebra").unwrap().typ(LocationType::Shrine).count());
+    }
+}
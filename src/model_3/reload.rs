@@ -0,0 +1,173 @@
+use std::fs;
+use std::time::SystemTime;
+
+use crate::*;
+use super::catalog::FILE_NAME_CATALOG;
+use super::data_source::DataSource;
+use super::game_record::{GameEvent, GameRecord};
+use super::model::*;
+use super::parse::{self, *};
+
+// The files that a model can be loaded from. A change to any of them triggers a reload.
+const WATCHED_FILE_NAMES: [&str; 10] = [FILE_NAME_CHARACTERS, FILE_NAME_LOCATIONS, FILE_NAME_DOG_TREASURES,
+    FILE_NAME_SHRINES, FILE_NAME_QUESTS, FILE_NAME_ITEMS, FILE_NAME_MAP_LOCATIONS, FILE_NAME_ALIASES,
+    FILE_NAME_USER_ALIASES, FILE_NAME_CATALOG];
+
+// Watches the catalog files behind a model so that edits made while a session is running can be
+// picked up without losing progress. There's nothing to watch for the embedded catalog.
+#[derive(Debug)]
+pub struct CatalogWatcher {
+    pub source: DataSource,
+    pub content_options: ContentOptions,
+    // The modification time of each file, or None if the file doesn't exist.
+    modified_times: BTreeMap<String, Option<SystemTime>>,
+}
+
+// What happened when the game record was applied to the reloaded catalog.
+#[derive(Debug, Default)]
+pub struct ReloadReport {
+    pub changed_file_names: Vec<String>,
+    pub applied_event_count: usize,
+    // Events whose character, location, quest, or item is no longer in the catalog. They stay in
+    // the game record so they'll apply again if the target comes back.
+    pub vanished_events: Vec<GameEvent>,
+}
+
+impl CatalogWatcher {
+    pub fn new(source: &DataSource, content_options: &ContentOptions) -> Self {
+        let mut watcher = Self {
+            source: source.clone(),
+            content_options: content_options.clone(),
+            modified_times: BTreeMap::new(),
+        };
+        watcher.modified_times = watcher.current_modified_times();
+        watcher
+    }
+
+    fn current_modified_times(&self) -> BTreeMap<String, Option<SystemTime>> {
+        match &self.source {
+            DataSource::Directory { path } => {
+                WATCHED_FILE_NAMES
+                    .iter()
+                    .map(|file_name| (file_name.to_string(), fs::metadata(path.join(file_name)).and_then(|metadata| metadata.modified()).ok()))
+                    .collect()
+            },
+            DataSource::Embedded => BTreeMap::new(),
+        }
+    }
+
+    // The files that were changed, added, or removed since the last check.
+    pub fn changed_file_names(&mut self) -> Vec<String> {
+        let modified_times = self.current_modified_times();
+        let changed_file_names = modified_times
+            .iter()
+            .filter(|(file_name, modified_time)| self.modified_times.get(*file_name) != Some(modified_time))
+            .map(|(file_name, _)| file_name.clone())
+            .collect();
+        self.modified_times = modified_times;
+        changed_file_names
+    }
+
+    // If any of the files have changed, reloads the catalog and replaces the model with one that
+    // has the game record applied to it. If the new catalog doesn't load, the current model is
+    // kept so that the session can go on while the file is fixed.
+    pub fn reload_if_changed(&mut self, model: &mut Model, game_record: &GameRecord) -> Option<Result<ReloadReport, Vec<ParseError>>> {
+        let changed_file_names = self.changed_file_names();
+        if changed_file_names.is_empty() {
+            return None;
+        }
+        Some(reload(&self.source, &self.content_options, game_record).map(|(new_model, mut report)| {
+            *model = new_model;
+            report.changed_file_names = changed_file_names;
+            report
+        }))
+    }
+
+    // For a session loop: reloads if anything changed and prints what happened. Returns whether
+    // the model was replaced.
+    pub fn reload_and_print(&mut self, model: &mut Model, game_record: &GameRecord) -> bool {
+        match self.reload_if_changed(model, game_record) {
+            Some(Ok(report)) => {
+                report.print();
+                true
+            },
+            Some(Err(errors)) => {
+                print_reload_errors(&errors);
+                false
+            },
+            None => false,
+        }
+    }
+}
+
+pub fn reload(source: &DataSource, content_options: &ContentOptions, game_record: &GameRecord) -> Result<(Model, ReloadReport), Vec<ParseError>> {
+    let mut model = Model::load_with_options(source, content_options)?;
+    let mut report = ReloadReport::default();
+    // The recorded events already include their predecessors, so they're applied as they are
+    // rather than through GameRecord::add_event().
+    for event in game_record.events.iter() {
+        match event.clone().apply(&mut model) {
+            Ok(()) => report.applied_event_count += 1,
+            Err(_) => report.vanished_events.push(event.clone()),
+        }
+    }
+    Ok((model, report))
+}
+
+impl ReloadReport {
+    pub fn print(&self) {
+        println!("Reloaded the catalog after changes to {}.", self.changed_file_names.iter().map(|file_name| format!("\"{}\"", file_name)).collect::<Vec<_>>().join(", "));
+        println!("Applied {} event(s).", self.applied_event_count);
+        if !self.vanished_events.is_empty() {
+            println!("{} event(s) refer to something that is no longer in the catalog:", self.vanished_events.len());
+            for event in self.vanished_events.iter() {
+                println!("\t{}", event);
+            }
+        }
+    }
+}
+
+// Keeps the current model after a failed reload and shows why.
+pub fn print_reload_errors(errors: &[ParseError]) {
+    println!("The catalog was changed but couldn't be reloaded, so the previous version is still in use.");
+    parse::print_errors(errors);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::time::Duration;
+    use super::super::data_source;
+    use super::super::game_record::GameEventType;
+
+    #[test]
+    fn changed_file_reloads_and_reports_vanished_events() {
+        let path = data_source::copy_embedded_files_to_temp("reload");
+        let source = DataSource::new_directory(&path);
+        let content_options = ContentOptions::default();
+        let mut model = Model::load_text(&source, &content_options).unwrap();
+        let mut game_record = GameRecord::new("Reload");
+        for (time, name) in [(10, "Akkala Span"), (20, "Deya Village Ruins")].iter() {
+            game_record.add_event(&mut model, GameEvent::new(*time, GameEventType::DiscoverLocation, name, None)).unwrap();
+        }
+        let mut watcher = CatalogWatcher::new(&source, &content_options);
+        assert!(watcher.reload_if_changed(&mut model, &game_record).is_none());
+
+        let locations_path = path.join(FILE_NAME_LOCATIONS);
+        let content = fs::read_to_string(&locations_path).unwrap().replace("Deya Village Ruins\n", "");
+        fs::write(&locations_path, content).unwrap();
+        // Make sure the change is seen even if the file system's clock is coarse.
+        let later = SystemTime::now() + Duration::from_secs(60);
+        OpenOptions::new().write(true).open(&locations_path).unwrap().set_modified(later).unwrap();
+
+        let report = watcher.reload_if_changed(&mut model, &game_record).unwrap().unwrap();
+        assert_eq!(report.changed_file_names, vec![FILE_NAME_LOCATIONS.to_string()]);
+        assert_eq!(report.applied_event_count, game_record.events.len() - 1);
+        assert_eq!(report.vanished_events.iter().map(|event| event.name()).collect::<Vec<_>>(), vec!["Deya Village Ruins"]);
+        assert_eq!(model.get_location("Akkala Span").discovered_time, 10);
+        assert!(model.try_location_id("Deya Village Ruins").is_err());
+        assert!(watcher.reload_if_changed(&mut model, &game_record).is_none());
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+
+use super::data_source::DataSource;
+use super::game_record::{GameEvent, GameRecord, NULL_TIME};
+use super::lookup::LookupError;
+use super::model::*;
+use super::parse::{self, ParseError};
+use super::runtime::GameClock;
+
+// Rebuilding a model from a saved game record by applying its events in order to a freshly loaded
+// catalog. Unlike a reload after a catalog edit, which skips events whose target has gone away,
+// a replay stops at the first event that doesn't fit what came before it, since that means the
+// record itself is wrong.
+//
+// An event whose prerequisites aren't in the record ahead of it isn't treated as wrong, since
+// records saved under older prerequisite rules are missing the events added by newer ones. The
+// missing events are applied just before it, as add_event() would have done, and returned as
+// warnings.
+
+pub struct Replay {
+    pub model: Model,
+    // The events the record was missing, each with the position of the event that needed it.
+    pub implied_events: Vec<(usize, GameEvent)>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Catalog(Vec<ParseError>),
+    // Boxed since the event and its problem are much bigger than a list of parse errors.
+    Event(Box<ReplayEventError>),
+}
+
+#[derive(Debug)]
+pub struct ReplayEventError {
+    // The position of the event in the record, starting at 0.
+    pub index: usize,
+    pub event: GameEvent,
+    pub problem: ReplayProblem,
+}
+
+#[derive(Debug)]
+pub enum ReplayProblem {
+    // The character, location, quest, or item isn't in the catalog or is the wrong kind of
+    // location.
+    Target(LookupError),
+    // Something like a shrine being completed a second time.
+    AlreadyHappened,
+    TimeWentBackward {
+        previous_time: usize,
+    },
+    MissingNumber,
+    // A counter like the Korok seeds that can only go up was set to the same or a lower number.
+    CountDidNotIncrease {
+        from: usize,
+        to: usize,
+    },
+}
+
+impl Model {
+    pub fn from_record(game_record: &GameRecord) -> Result<Replay, ReplayError> {
+        Self::from_record_with_options(&DataSource::new_default(), &ContentOptions::default(), game_record)
+    }
+
+    pub fn from_record_with_options(source: &DataSource, content_options: &ContentOptions, game_record: &GameRecord) -> Result<Replay, ReplayError> {
+        let mut model = Self::load_with_options(source, content_options).map_err(ReplayError::Catalog)?;
+        let mut implied_events = vec![];
+        let mut previous_time = 0;
+        for (index, event) in game_record.events.iter().enumerate() {
+            let event_error = |problem| ReplayError::Event(Box::new(ReplayEventError {
+                index,
+                event: event.clone(),
+                problem,
+            }));
+            if event.time() < previous_time {
+                return Err(event_error(ReplayProblem::TimeWentBackward { previous_time }));
+            }
+            previous_time = event.time();
+            event.check_replay(&mut model).map_err(event_error)?;
+            // The expanded list ends with the event itself.
+            let mut events = event.expand(&model).map_err(|e| event_error(ReplayProblem::Target(e)))?;
+            let implied_count = events.len() - 1;
+            for (event_index, one_event) in events.iter_mut().enumerate() {
+                one_event.apply(&mut model).map_err(|e| event_error(ReplayProblem::Target(e)))?;
+                if event_index < implied_count {
+                    implied_events.push((index, one_event.clone()));
+                }
+            }
+        }
+        Ok(Replay {
+            model,
+            implied_events,
+        })
+    }
+}
+
+impl GameEvent {
+    // Whether the event could have happened next given the events applied to the model so far.
+    fn check_replay(&self, model: &mut Model) -> Result<(), ReplayProblem> {
+        self.check_target(model).map_err(ReplayProblem::Target)?;
+        let current_value = *self.target_value(model).map_err(ReplayProblem::Target)?;
+        if self.is_count() {
+            let number = self.number().ok_or(ReplayProblem::MissingNumber)?;
+            if self.is_increasing_count() && number <= current_value {
+                return Err(ReplayProblem::CountDidNotIncrease { from: current_value, to: number });
+            }
+        } else if current_value != NULL_TIME {
+            return Err(ReplayProblem::AlreadyHappened);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Catalog(errors) => {
+                write!(f, "The catalog couldn't be loaded: {}", errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(" "))
+            },
+            ReplayError::Event(event_error) => {
+                let ReplayEventError { index, event, problem } = event_error.as_ref();
+                write!(f, "Event {} ({}) ", index, event)?;
+                match problem {
+                    ReplayProblem::Target(e) => write!(f, "doesn't match the catalog: {}", e),
+                    ReplayProblem::AlreadyHappened => write!(f, "already happened earlier in the record."),
+                    ReplayProblem::TimeWentBackward { previous_time } => write!(f, "is earlier than the event before it at {}.", GameClock::format_time(*previous_time).trim()),
+                    ReplayProblem::MissingNumber => write!(f, "has no number."),
+                    ReplayProblem::CountDidNotIncrease { from, to } => write!(f, "changes the count from {} to {}.", from, to),
+                }
+            },
+        }
+    }
+}
+
+pub fn print_replay_error(error: &ReplayError) {
+    match error {
+        ReplayError::Catalog(errors) => parse::print_errors(errors),
+        ReplayError::Event(_) => println!("{}", error),
+    }
+}
+
+pub fn print_implied_events(replay: &Replay) {
+    for (index, event) in replay.implied_events.iter() {
+        println!("Event {} needed an earlier event that isn't in the record, so it was added: {}", index, event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::game_record::GameEventType;
+
+    fn replay(events: Vec<GameEvent>) -> Result<Replay, ReplayError> {
+        let mut game_record = GameRecord::new("Test");
+        game_record.events = events;
+        Model::from_record_with_options(&DataSource::new_embedded(), &ContentOptions::default(), &game_record)
+    }
+
+    fn problem_index(result: Result<Replay, ReplayError>) -> (usize, ReplayProblem) {
+        match result {
+            Err(ReplayError::Event(event_error)) => (event_error.index, event_error.problem),
+            Err(e) => panic!("{}", e),
+            Ok(_) => panic!("The replay should have failed."),
+        }
+    }
+
+    #[test]
+    fn replay_stops_at_the_first_inconsistent_event() {
+        // A region can be discovered without anything coming before it.
+        let discover = |time| GameEvent::new(time, GameEventType::DiscoverLocation, "Hebra", None);
+        let korok_seeds = |time, number| GameEvent::new(time, GameEventType::KorokSeed, "", Some(number));
+
+        let model = replay(vec![discover(10), korok_seeds(20, 5), korok_seeds(30, 6)]).unwrap().model;
+        assert_eq!(6, model.korok_seeds);
+        assert!(model.get_location("Hebra").is_discovered());
+
+        let (index, problem) = problem_index(replay(vec![discover(10), korok_seeds(20, 5), korok_seeds(30, 3), discover(40)]));
+        assert_eq!(2, index);
+        assert!(matches!(problem, ReplayProblem::CountDidNotIncrease { from: 5, to: 3 }));
+
+        let (index, problem) = problem_index(replay(vec![korok_seeds(20, 5), discover(30), discover(40)]));
+        assert_eq!(2, index);
+        assert!(matches!(problem, ReplayProblem::AlreadyHappened));
+
+        let (index, problem) = problem_index(replay(vec![discover(30), korok_seeds(20, 5)]));
+        assert_eq!(1, index);
+        assert!(matches!(problem, ReplayProblem::TimeWentBackward { previous_time: 30 }));
+
+        let (index, problem) = problem_index(replay(vec![discover(10), GameEvent::new(20, GameEventType::DiscoverLocation, "Nowhere", None)]));
+        assert_eq!(1, index);
+        assert!(matches!(problem, ReplayProblem::Target(LookupError::Unknown { .. })));
+    }
+
+    #[test]
+    fn missing_prerequisites_in_an_old_record_are_implied() {
+        let complete_shrine = GameEvent::new(10, GameEventType::CompleteShrine, "Mezza Lo Shrine", None);
+        let replay = replay(vec![complete_shrine.clone()]).unwrap();
+        assert!(replay.model.get_shrine("Mezza Lo Shrine").is_completed());
+        let expected = complete_shrine.expand(&Model::load_text(&DataSource::new_embedded(), &ContentOptions::default()).unwrap()).unwrap();
+        assert_eq!(expected.len() - 1, replay.implied_events.len());
+        assert!(replay.implied_events.iter().all(|(index, event)| *index == 0 && event.is_predecessor()));
+    }
+}
@@ -0,0 +1,412 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::*;
+use super::alias::AliasList;
+use super::model::*;
+use super::parse::*;
+
+// Writes the model back out in the same text formats that model_3::parse reads. The written files
+// have the same lines as the ones the model was loaded from apart from comments and blank lines,
+// though the entries may come out in a different order than in the hand-edited files. A model
+// that was loaded without some of the content packs can't be written since the files would
+// silently lose everything from those packs.
+pub fn write_catalog<P: AsRef<Path>>(model: &Model, path: P) -> io::Result<()> {
+    if !model.content_options.includes_all() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "the catalog can only be written from a model that includes every content pack"));
+    }
+    let path = path.as_ref();
+    fs::create_dir_all(path)?;
+    for (file_name, content) in [
+        (FILE_NAME_CHARACTERS, format_characters(model)),
+        (FILE_NAME_LOCATIONS, format_locations(model)),
+        (FILE_NAME_DOG_TREASURES, format_dog_treasures(model)),
+        (FILE_NAME_SHRINES, format_shrines(model)),
+        (FILE_NAME_QUESTS, format_quests(model)),
+        (FILE_NAME_ITEMS, format_items(model)),
+        (FILE_NAME_MAP_LOCATIONS, format_map_locations(model)),
+        (FILE_NAME_ALIASES, format_aliases(model)),
+    ] {
+        fs::write(path.join(file_name), content)?;
+    }
+    Ok(())
+}
+
+pub fn format_characters(model: &Model) -> String {
+    let mut characters_by_race: BTreeMap<Race, Vec<&Character>> = BTreeMap::new();
+    for character in model.characters.values() {
+        characters_by_race.entry(character.race.clone()).or_default().push(character);
+    }
+    let mut sections = vec![];
+    for (race, characters) in characters_by_race.iter() {
+        let mut s = format!("{}{}\n", PREFIX_HEADER, race_header_name(race));
+        for character in characters.iter() {
+            let tags = [(character.main, "main"), (character.champion, "champion"), (character.merchant, "merchant"), (!character.alive, "dead")]
+                .iter()
+                .filter(|(is_set, _)| *is_set)
+                .map(|(_, tag)| *tag)
+                .collect::<Vec<_>>();
+            // Everyone under the Amiibo header is already Amiibo content.
+            let marker = if *race == Race::Amiibo { "" } else { content_pack_marker(character.content_pack) };
+            let tags = if tags.is_empty() { "".to_string() } else { format!(": {}", tags.join(", ")) };
+            s.push_str(&format!("{}{}{}\n", character.name, marker, tags));
+        }
+        sections.push(s);
+    }
+    sections.join("\n")
+}
+
+// A race like SheikahMonk has the header "#Sheikah Monk".
+fn race_header_name(race: &Race) -> String {
+    let mut name = String::new();
+    for c in race.to_string().chars() {
+        if c.is_uppercase() && !name.is_empty() {
+            name.push(' ');
+        }
+        name.push(c);
+    }
+    name
+}
+
+// Regions are "#" headers and areas are ">" subheaders, each followed by the locations in them.
+// The type of most locations comes from the end of the name, as in "Akkala Tower", but towns need
+// the " (town)" suffix. A content pack marker is only written where it differs from the parent's.
+pub fn format_locations(model: &Model) -> String {
+    let mut sections = vec![];
+    for region in model.locations.values().filter(|location| location.typ == LocationType::Region) {
+        let mut s = format!("{}{}{}\n", PREFIX_HEADER, region.name, content_pack_marker(region.content_pack));
+        for area in children_of(model, region) {
+            s.push_str(&format!("\n{}{}{}{}\n", PREFIX_SUBHEADER, area.name, town_suffix(area), inherited_marker(area, region)));
+            for location in children_of(model, area) {
+                s.push_str(&format!("{}{}{}\n", location.name, town_suffix(location), inherited_marker(location, area)));
+            }
+        }
+        sections.push(s);
+    }
+    sections.join("\n")
+}
+
+fn children_of<'a>(model: &'a Model, parent: &Location) -> Vec<&'a Location> {
+    parent.child_locations.iter().map(|child_id| model.locations.by_id(*child_id)).collect()
+}
+
+fn town_suffix(location: &Location) -> &str {
+    if location.typ == LocationType::Town { SUFFIX_TOWN } else { "" }
+}
+
+fn inherited_marker<'a>(location: &Location, parent: &Location) -> &'a str {
+    if location.content_pack == parent.content_pack { "" } else { content_pack_marker(location.content_pack) }
+}
+
+pub fn format_dog_treasures(model: &Model) -> String {
+    model.locations
+        .values()
+        .filter_map(|location| location.dog_treasure.as_ref().map(|treasure| format!("{}: {}\n", location.name, treasure)))
+        .collect()
+}
+
+pub fn format_shrines(model: &Model) -> String {
+    model.locations
+        .values()
+        .filter(|location| location.typ == LocationType::Shrine)
+        .filter_map(|location| location.challenge.as_ref().map(|challenge| format!("{}: {}\n", location.name, challenge)))
+        .collect()
+}
+
+// The region is only written when there's no start location since otherwise it comes from the
+// start location.
+fn format_quest_notes(model: &Model, quest: &Quest) -> String {
+    let mut parts = vec![];
+    if let Some(notes) = &quest.notes {
+        parts.push(notes.clone());
+    }
+    if let Some(giver) = quest.giver {
+        parts.push(format!("{}: {}", QUEST_NOTE_GIVER, model.character_name(giver)));
+    }
+    match (quest.start_location, quest.region) {
+        (Some(start_location), _) => parts.push(format!("{}: {}", QUEST_NOTE_LOCATION, model.location_name(start_location))),
+        (None, Some(region)) => parts.push(format!("{}: {}", QUEST_NOTE_REGION, model.location_name(region))),
+        (None, None) => {},
+    }
+    if parts.is_empty() {
+        "".to_string()
+    } else {
+        format!(" ({})", parts.join(&format!("{} ", SEPARATOR_QUEST_NOTES)))
+    }
+}
+
+pub fn format_quests(model: &Model) -> String {
+    let mut main = format!("{}Main\n", PREFIX_HEADER);
+    let mut shrine = format!("{}Shrine\n", PREFIX_HEADER);
+    let mut side = format!("{}Side\n", PREFIX_HEADER);
+    for quest in model.quests.values() {
+        let marker = content_pack_marker(quest.content_pack);
+        match quest.typ {
+            QuestType::Main => main.push_str(&format!("{}{}\n", quest.name, marker)),
+            QuestType::Shrine => shrine.push_str(&format!("{}{}: {}\n", quest.name, marker, model.location_name(quest.shrine.unwrap()))),
+            QuestType::Side => side.push_str(&format!("{}{}{}\n", quest.name, marker, format_quest_notes(model, quest))),
+        }
+    }
+    [main, shrine, side].join("\n")
+}
+
+// The "Items in Order" section comes first since the other sections can only name items that are
+// already defined. Each line for an item from a content pack is marked so that the file can still
+// be loaded without that pack.
+pub fn format_items(model: &Model) -> String {
+    let items = model.items_in_order();
+    let mut s = format!("{}{}\n", PREFIX_HEADER, SECTION_ITEMS_IN_ORDER);
+    let mut subsection_name = None;
+    for item in items.iter() {
+        let recipe = model.recipes.get(&item.name.to_lowercase());
+        let item_subsection_name = item_subsection_name(item, recipe);
+        if subsection_name != Some(item_subsection_name) {
+            s.push_str(&format!("{}{}\n", PREFIX_SUBHEADER, item_subsection_name));
+            subsection_name = Some(item_subsection_name);
+        }
+        s.push_str(&format!("{}{}", item.name, content_pack_marker(item.content_pack)));
+        if let Some(recipe) = recipe.filter(|recipe| !recipe.ingredients.is_empty()) {
+            let ingredients = recipe.ingredients.iter().map(|ingredient| ingredient.alternatives.join("/")).collect::<Vec<_>>();
+            s.push_str(&format!("{}{}", SEPARATOR_COLUMN, ingredients.join(", ")));
+            if let Some(method) = &recipe.method {
+                s.push_str(&format!(" {}", method));
+            }
+        }
+        s.push('\n');
+    }
+
+    s.push_str(&format!("\n{}{}\n", PREFIX_HEADER, SECTION_ITEM_EFFECTS));
+    for item in items.iter() {
+        if let Some(description) = item.effect_description.clone().or_else(|| effect_description(item)) {
+            s.push_str(&format!("{}{}{}{}\n", item.name, content_pack_marker(item.content_pack), SEPARATOR_COLUMN, description));
+        }
+    }
+
+    s.push_str(&format!("\n{}{}\n", PREFIX_HEADER, SECTION_PURCHASE_AND_UPGRADE));
+    for item in items.iter().filter(|item| item.purchase_price.is_some() || !item.upgrade_prices.is_empty()) {
+        s.push_str(&format!("{}{}", item.name, content_pack_marker(item.content_pack)));
+        if let Some(price) = item.purchase_price.as_ref().filter(|_| item.has_purchase_price()) {
+            s.push_str(&format!(": {}", format_price(price)));
+        }
+        s.push('\n');
+        for (index, price) in item.upgrade_prices.iter().enumerate() {
+            s.push_str(&format!("\t{}: {}\n", index + 1, format_price(price)));
+        }
+    }
+
+    s.push_str(&format!("\n{}{}\n", PREFIX_HEADER, SECTION_MON_SELL_PRICES));
+    for item in items.iter() {
+        if let Some(mon_price) = item.mon_sell_price {
+            if let Some(name) = mon_price_name(model, item) {
+                s.push_str(&format!("{}{}{}{}\n", name, content_pack_marker(item.content_pack), SEPARATOR_COLUMN, mon_price));
+            }
+        }
+    }
+
+    s.push_str(&format!("\n{}{}\n", PREFIX_HEADER, SECTION_ARMOR_SETS));
+    for armor_set in model.armor_sets.values() {
+        let set_bonus = armor_set.set_bonus.as_ref().map_or("".to_string(), |set_bonus| format!(": {}", set_bonus));
+        s.push_str(&format!("{}{}{}{}\n", PREFIX_SUBHEADER, armor_set.name, content_pack_marker(armor_set.content_pack), set_bonus));
+        for slot in [ArmorSlot::Head, ArmorSlot::Body, ArmorSlot::Legs].iter() {
+            if let Some(item_id) = armor_set.get_piece(*slot) {
+                s.push_str(&format!("{}: {}\n", slot.variant_to_string().to_lowercase(), model.items.by_id(item_id).name));
+            }
+        }
+        for location_id in armor_set.shop_locations.iter() {
+            s.push_str(&format!("{}: {}\n", ARMOR_SET_SHOP, model.location_name(*location_id)));
+        }
+    }
+    s
+}
+
+// Every item under a food subsection gets a recipe, so the recipe type says which of those
+// subsections a food item goes under.
+fn item_subsection_name<'a>(item: &Item, recipe: Option<&Recipe>) -> &'a str {
+    match (item.typ, recipe.map(|recipe| recipe.typ)) {
+        (_, Some(RecipeType::Dish)) => "Dishes",
+        (_, Some(RecipeType::Elixir)) => "Elixers",
+        (_, Some(RecipeType::Roasted)) => "Roasted Foods",
+        (_, Some(RecipeType::Frozen)) => "Frozen Foods",
+        (ItemType::Money, None) => "Money",
+        (ItemType::Material, None) => "Materials",
+        (ItemType::Weapon, None) => "Weapons",
+        (ItemType::Bow, None) => "Bows",
+        (ItemType::Arrow, None) => "Arrows",
+        (ItemType::Shield, None) => "Shields",
+        (ItemType::Armor, None) => "Armor",
+        (ItemType::Food, None) => "Dishes",
+        (ItemType::KeyItem, None) => "Key Items",
+    }
+}
+
+// The name an item's Mon price is listed under. The dragon parts all sell for the same price so
+// they share a line like "Dragon's Scale", written for the first dragon and skipped for the
+// others. A dragon part with its own price keeps its own name.
+fn mon_price_name(model: &Model, item: &Item) -> Option<String> {
+    let dragon = match DRAGON_NAMES.iter().find(|dragon| item.name.contains(*dragon)) {
+        Some(dragon) => dragon,
+        None => return Some(item.name.clone()),
+    };
+    let shared_name = item.name.replace(dragon, DRAGON_PLACEHOLDER);
+    let shared = DRAGON_NAMES
+        .iter()
+        .map(|other| model.items.get(&shared_name.replace(DRAGON_PLACEHOLDER, other).to_lowercase()))
+        .all(|other| other.is_some_and(|other| other.mon_sell_price == item.mon_sell_price));
+    match (shared, *dragon == DRAGON_NAMES[0]) {
+        (true, true) => Some(shared_name),
+        (true, false) => None,
+        (false, _) => Some(item.name.clone()),
+    }
+}
+
+// A plain description for an item that doesn't have a hand-written one, such as an item added
+// through the structured catalog, that Effect::from_description reads back as the same effect.
+// The effects with no description here never come from the Items file.
+fn effect_description(item: &Item) -> Option<String> {
+    let effect = item.effect.and_then(|effect| match effect {
+        Effect::RestoreHearts => Some("hearts restored"),
+        Effect::TemporaryHearts => Some("temporary additional hearts"),
+        Effect::RestoreStamina => Some("stamina restored"),
+        Effect::TemporaryStamina => Some("temporary stamina increase"),
+        Effect::MovementSpeed => Some("movement speed increased"),
+        Effect::Fireproof => Some("fireproof"),
+        Effect::ColdResistance => Some("cold resistance"),
+        Effect::HeatResistance => Some("heat resistance"),
+        Effect::ShockResistance => Some("electric resistance"),
+        Effect::Attack => Some("attack increased"),
+        Effect::Defense => Some("defense increased"),
+        Effect::Stealth => Some("stealth increased"),
+        _ => None,
+    });
+    match (item.is_monster_part, effect) {
+        (true, Some(effect)) => Some(format!("{} for {}", PHRASE_MONSTER_PART, effect)),
+        (true, None) => Some(PHRASE_MONSTER_PART.to_string()),
+        (false, Some(effect)) => Some(effect.to_string()),
+        (false, None) => None,
+    }
+}
+
+fn format_price(price: &Price) -> String {
+    price.components
+        .iter()
+        .map(|component| match component {
+            PriceComponent::Item { name, quantity } => format!("{} {}", quantity, name),
+            PriceComponent::Requirement { name } => name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Every entry from the map file is kept in the model, whether or not it was joined to a location,
+// and written in the file's own spacing.
+pub fn format_map_locations(model: &Model) -> String {
+    model.map_entries
+        .iter()
+        .map(|entry| {
+            // The names are quoted and escaped as JSON strings, which can't fail.
+            let quote = |name: &String| serde_json::to_string(name).unwrap();
+            format!("{{\"internal_name\":{}, \"display_name\":{}, \"x\":{}, \"y\":{}}},\n", quote(&entry.internal_name), quote(&entry.display_name), entry.x, entry.y)
+        })
+        .collect()
+}
+
+// The internal names from the map file are left out since they're added again when the map file
+// is loaded, unless the entry's display name isn't the location's name and so the map file is
+// joined through the alias. Aliases from the player's own file are written along with the shipped
+// ones.
+pub fn format_aliases(model: &Model) -> String {
+    let is_added_by_map_file = |location: &Location, alias: &str| location.internal_name.as_ref().is_some_and(|name| name.eq_ignore_ascii_case(alias))
+        && model.map_entries
+            .iter()
+            .any(|entry| entry.internal_name.eq_ignore_ascii_case(alias) && entry.display_name.trim().eq_ignore_ascii_case(&location.name));
+    let mut sections: BTreeMap<AliasList, String> = BTreeMap::new();
+    for (list, alias, keys) in model.aliases.iter() {
+        for key in keys.iter() {
+            let name = match list {
+                AliasList::Character => model.characters.get(key).map(|character| &character.name),
+                AliasList::Location => model.locations
+                    .get(key)
+                    .filter(|location| !is_added_by_map_file(location, alias))
+                    .map(|location| &location.name),
+                AliasList::Quest => model.quests.get(key).map(|quest| &quest.name),
+                AliasList::Item => model.items.get(key).map(|item| &item.name),
+            };
+            if let Some(name) = name {
+                let s = sections.entry(list).or_insert_with(|| format!("{}{}\n", PREFIX_HEADER, list.section_name()));
+                s.push_str(&format!("{}: {}\n", alias, name));
+            }
+        }
+    }
+    sections.values().cloned().collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::path::PathBuf;
+    use super::super::catalog::Catalog;
+    use super::super::data_source::{self, DataSource};
+
+    // The structured catalog has every part of the model that the text files describe, so two
+    // models that give the same catalog are the same.
+    fn catalog_json(model: &Model) -> String {
+        serde_json::to_string_pretty(&Catalog::from_model(model)).unwrap()
+    }
+
+    fn temp_path(dir_name: &str) -> PathBuf {
+        env::temp_dir().join(format!("botw_{}_{}", dir_name, std::process::id()))
+    }
+
+    #[test]
+    fn written_catalog_loads_as_the_same_model() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::new_all()).unwrap();
+        let path = temp_path("serialize_first");
+        write_catalog(&model, &path).unwrap();
+        let reloaded = Model::load_text(&DataSource::new_directory(&path), &ContentOptions::new_all()).unwrap();
+        assert_eq!(catalog_json(&model), catalog_json(&reloaded));
+        assert_eq!(model.aliases.len(), reloaded.aliases.len());
+
+        // Once the files are in the written order, writing them again doesn't change them.
+        let second_path = temp_path("serialize_second");
+        write_catalog(&reloaded, &second_path).unwrap();
+        for file_name in fs::read_dir(&path).unwrap().map(|entry| entry.unwrap().file_name()) {
+            assert_eq!(fs::read_to_string(path.join(&file_name)).unwrap(), fs::read_to_string(second_path.join(&file_name)).unwrap(), "{:?}", file_name);
+        }
+        fs::remove_dir_all(&path).unwrap();
+        fs::remove_dir_all(&second_path).unwrap();
+    }
+
+    // The lines of a catalog file other than comments and blank lines, sorted.
+    fn sorted_lines(text: &str) -> Vec<&str> {
+        let mut lines = text.lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with(PREFIX_COMMENT))
+            .collect::<Vec<_>>();
+        lines.sort_unstable();
+        lines
+    }
+
+    #[test]
+    fn written_files_match_the_shipped_files_apart_from_order() {
+        let source = DataSource::new_embedded();
+        let model = Model::load_text(&source, &ContentOptions::new_all()).unwrap();
+        let path = temp_path("serialize_shipped");
+        write_catalog(&model, &path).unwrap();
+        for file_name in data_source::text_file_names() {
+            let shipped = read_file(&source, file_name).unwrap();
+            let written = fs::read_to_string(path.join(file_name)).unwrap();
+            assert_eq!(sorted_lines(&shipped), sorted_lines(&written), "{}", file_name);
+        }
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn model_without_every_content_pack_is_not_written() {
+        let model = Model::load_text(&DataSource::new_embedded(), &ContentOptions::new_base_game()).unwrap();
+        let path = temp_path("serialize_base_game");
+        let error = write_catalog(&model, &path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+}
@@ -1,6 +1,12 @@
 use rand::Rng;
 use super::command::*;
 use super::model::*;
+use super::parse;
+use super::data_source::DataSource;
+use super::journal::{self, Journal, JOURNAL_FILE_EXTENSION};
+use super::reload::CatalogWatcher;
+use super::replay;
+use std::env;
 use std::time::Instant;
 use crate::model_3::game_record::GameRecord;
 
@@ -9,14 +15,30 @@ pub fn test_many_actions() {
     let partial_name_substring_length = 2;
 
     let start_time = Instant::now();
-    let mut model = Model::new();
+    let mut model = match Model::new() {
+        Ok(model) => model,
+        Err(errors) => {
+            parse::print_errors(&errors);
+            return;
+        }
+    };
     let mut game_record = GameRecord::new("Sim");
+    // Model::new() loads the default source with the default content options.
+    let mut catalog_watcher = CatalogWatcher::new(&DataSource::new_default(), &ContentOptions::default());
+    let journal_path = env::temp_dir().join(format!("botw_sim.{}", JOURNAL_FILE_EXTENSION));
+    let mut journal = match Journal::create(&journal_path, &game_record) {
+        Ok(journal) => journal,
+        Err(e) => {
+            println!("The journal couldn't be created: {}", e);
+            return;
+        }
+    };
 
     dbg!(Instant::now() - start_time);
 
     let start_time = Instant::now();
 
-    let mut special_commands = vec!["bl", "bo", "c", "die", "he", "k", "sh", "st", "we"]
+    let mut special_commands = ["bl", "bo", "c", "die", "he", "k", "sh", "st", "we"]
         .iter()
         .map(|x| x.to_string())
         .collect::<Vec<_>>();
@@ -41,11 +63,12 @@ pub fn test_many_actions() {
     let start_time = Instant::now();
     let mut rng = rand::thread_rng();
     let mut game_time = 0;
+    let mut last_reload_check_time = Instant::now();
     loop {
         let partial_name_index = rng.gen_range(0..partial_names.len());
         let partial_name = &partial_names[partial_name_index];
         //rintln!("{}", partial_name);
-        let mut command_set = CommandSet::new(&partial_name, None);
+        let mut command_set = CommandSet::new(partial_name, None);
         command_set.include_empty_targets = false;
         command_set.add_to_special_commands = true;
         command_set.generate(&model);
@@ -64,10 +87,29 @@ pub fn test_many_actions() {
         assert!(command_count > 0);
         // command_number is 1-based.
         let command_number = rng.gen_range(1..=command_count);
-        command_set.apply_command(&mut model, &mut game_record, game_time, command_number);
+        if let Err(e) = command_set.apply_command(&mut model, &mut game_record, &mut journal, game_time, command_number) {
+            println!("{}", e);
+        }
+        // Now and then take back an event and sometimes put it back again.
+        if rng.gen_range(0..20) == 0 {
+            if let Err(e) = journal.undo(&mut game_record, &mut model) {
+                println!("{}", e);
+            }
+            if rng.gen_range(0..2) == 0 {
+                if let Err(e) = journal.redo(&mut game_record, &mut model) {
+                    println!("{}", e);
+                }
+            }
+        }
 
         game_time += rng.gen_range(0..60);
 
+        // Pick up edits to the catalog files made while the session is running.
+        if (Instant::now() - last_reload_check_time).as_secs() >= 1 {
+            catalog_watcher.reload_and_print(&mut model, &game_record);
+            last_reload_check_time = Instant::now();
+        }
+
         if (Instant::now() - start_time).as_secs() >= max_seconds {
             break;
         }
@@ -89,6 +131,24 @@ pub fn test_many_actions() {
     let new_game_record: GameRecord = serde_json::from_str(&json).unwrap();
     dbg!(Instant::now() - start_time);
     dbg!(new_game_record.events.len());
+
+    // Reading the journal back should give the same record.
+    match journal::recover(journal.path()) {
+        Ok(recovery) => assert_eq!(game_record.events.len(), recovery.record.events.len()),
+        Err(e) => println!("The journal couldn't be read: {}", e),
+    }
+
+    // Rebuilding the model from the saved record should give the same progress.
+    let start_time = Instant::now();
+    match Model::from_record(&new_game_record) {
+        Ok(replay) => {
+            dbg!(Instant::now() - start_time);
+            replay::print_implied_events(&replay);
+            assert_eq!(model.completion().tally(), replay.model.completion().tally());
+            assert_eq!(model.korok_seeds, replay.model.korok_seeds);
+        },
+        Err(e) => replay::print_replay_error(&e),
+    }
 }
 
 /*
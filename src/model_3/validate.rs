@@ -0,0 +1,206 @@
+use std::fmt;
+
+use super::data_source::DataSource;
+use super::model::*;
+use super::parse::{self, ParseError};
+
+#[derive(Debug)]
+pub struct Finding {
+    pub typ: FindingType,
+    pub file_name: Option<String>,
+    pub line_number: Option<usize>,
+    pub name: String,
+    pub detail: String,
+}
+
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum FindingType {
+    ParseError,
+    DanglingReference,
+    Duplicate,
+    ExtraSpaces,
+    ShrineWithoutChallenge,
+}
+
+pub fn main(path: Option<&str>) -> usize {
+    let source = path.map_or_else(DataSource::new_default, DataSource::new_directory);
+    println!("Validating {}.", source.description());
+    let findings = validate_source(&source);
+    for finding in findings.iter() {
+        println!("\t{}", finding);
+    }
+    println!("{} finding(s).", findings.len());
+    findings.len()
+}
+
+// Load the catalog from the source and check it. If the catalog can't be loaded the parse errors
+// are returned as findings since the remaining checks need a complete model.
+pub fn validate_source(source: &DataSource) -> Vec<Finding> {
+    match Model::load(source) {
+        Ok(model) => {
+            let mut findings = validate_model(&model);
+            findings.append(&mut validate_map_locations(source, &model));
+            findings.append(&mut validate_purchase_references(&model));
+            findings
+        },
+        Err(errors) => errors.iter().map(Finding::from_parse_error).collect(),
+    }
+}
+
+pub fn validate_model(model: &Model) -> Vec<Finding> {
+    let mut findings = vec![];
+    for character in model.characters.values() {
+        check_name(&mut findings, "character", &character.name);
+    }
+    // References between entries are IDs that were checked when the model was loaded, so an ID
+    // that's out of range would mean a bug rather than a problem in the files.
+    for (location_id, location) in model.locations.iter_ids() {
+        check_name(&mut findings, "location", &location.name);
+        // The parent and child links should agree with each other.
+        if let Some(parent_id) = location.parent_location {
+            match model.locations.get_by_id(parent_id) {
+                Some(parent) if parent.child_locations.contains(&location_id) => {},
+                Some(parent) => findings.push(Finding::new(FindingType::DanglingReference, &location.name, &format!("parent location \"{}\" does not list this location as a child", parent.name))),
+                None => findings.push(Finding::new(FindingType::DanglingReference, &location.name, &format!("parent location {:?} is not a known location", parent_id))),
+            }
+        }
+        for child_id in location.child_locations.iter() {
+            match model.locations.get_by_id(*child_id) {
+                Some(child) if child.parent_location == Some(location_id) => {},
+                Some(child) => findings.push(Finding::new(FindingType::DanglingReference, &location.name, &format!("child location \"{}\" does not have this location as its parent", child.name))),
+                None => findings.push(Finding::new(FindingType::DanglingReference, &location.name, &format!("child location {:?} is not a known location", child_id))),
+            }
+        }
+        if location.typ == LocationType::Shrine {
+            if location.challenge.is_none() {
+                findings.push(Finding::new(FindingType::ShrineWithoutChallenge, &location.name, "shrine has no challenge"));
+            }
+            if let Some(quest_id) = location.quest {
+                match model.quests.get_by_id(quest_id) {
+                    Some(quest) if quest.shrine == Some(location_id) => {},
+                    Some(quest) => findings.push(Finding::new(FindingType::DanglingReference, &location.name, &format!("quest \"{}\" does not refer back to this shrine", quest.name))),
+                    None => findings.push(Finding::new(FindingType::DanglingReference, &location.name, &format!("quest {:?} is not a known quest", quest_id))),
+                }
+            }
+        }
+    }
+    for quest in model.quests.values() {
+        check_name(&mut findings, "quest", &quest.name);
+        if let Some(shrine_id) = quest.shrine {
+            match model.locations.get_by_id(shrine_id) {
+                Some(location) if location.typ == LocationType::Shrine => {},
+                Some(location) => findings.push(Finding::new(FindingType::DanglingReference, &quest.name, &format!("\"{}\" is not a shrine", location.name))),
+                None => findings.push(Finding::new(FindingType::DanglingReference, &quest.name, &format!("shrine {:?} is not a known location", shrine_id))),
+            }
+        }
+        if let Err(error) = model.check_quest_links(quest) {
+            findings.push(Finding::new(FindingType::DanglingReference, &quest.name, &format!("expected {}", error)));
+        }
+    }
+    for item in model.items.values() {
+        check_name(&mut findings, "item", &item.name);
+    }
+    findings
+}
+
+// The parsers trim each name, so only spaces inside a name can be left over. Curly apostrophes
+// aren't reported since the catalog files use them in quest names to match the game.
+fn check_name(findings: &mut Vec<Finding>, label: &str, name: &str) {
+    if name.contains("  ") {
+        findings.push(Finding::new(FindingType::ExtraSpaces, name, &format!("{} name has two spaces in a row", label)));
+    }
+}
+
+// Every entry in the datamined map file should name a location in the catalog. Catalog
+// locations without map coordinates aren't reported since most of them aren't map locations.
+pub fn validate_map_locations(source: &DataSource, model: &Model) -> Vec<Finding> {
+    let checked_match;
+    let map_match = match &model.map_location_match {
+        Some(map_match) => map_match,
+        None => match parse::check_map_locations(model, source) {
+            Ok(map_match) => {
+                checked_match = map_match;
+                &checked_match
+            },
+            Err(errors) => return errors.iter().map(Finding::from_parse_error).collect(),
+        },
+    };
+    let finding = |typ, name: &String, detail| Finding::new_in_file(typ, parse::FILE_NAME_MAP_LOCATIONS, name, detail);
+    map_match.unmatched_map_names
+        .iter()
+        .map(|name| finding(FindingType::DanglingReference, name, "map location is not a known location"))
+        .chain(map_match.duplicate_map_names
+            .iter()
+            .map(|name| finding(FindingType::Duplicate, name, "map location is listed more than once")))
+        .collect()
+}
+
+// Prices in the Purchase and Upgrade section of the Items file can name a requirement such as
+// "Locked Mementos" rather than an item. Each of these should be a known quest, shrine, or
+// milestone, by name or alias. Item names in prices are already checked when the file is loaded.
+pub fn validate_purchase_references(model: &Model) -> Vec<Finding> {
+    let mut findings = vec![];
+    for item in model.items_in_order() {
+        for price in item.purchase_price.iter().chain(item.upgrade_prices.iter()) {
+            for component in price.components.iter() {
+                if let PriceComponent::Requirement { name } = component {
+                    if !model.is_known_requirement(name) {
+                        findings.push(Finding::new_in_file(FindingType::DanglingReference, parse::FILE_NAME_ITEMS, name, &format!("price for \"{}\" names something that is not a known quest, location, or milestone", item.name)));
+                    }
+                }
+            }
+        }
+    }
+    findings
+}
+
+impl Finding {
+    pub fn new(typ: FindingType, name: &str, detail: &str) -> Self {
+        Self {
+            typ,
+            file_name: None,
+            line_number: None,
+            name: name.to_string(),
+            detail: detail.to_string(),
+        }
+    }
+
+    pub fn new_in_file(typ: FindingType, file_name: &str, name: &str, detail: &str) -> Self {
+        let mut finding = Self::new(typ, name, detail);
+        finding.file_name = Some(file_name.to_string());
+        finding
+    }
+
+    pub fn from_parse_error(error: &ParseError) -> Self {
+        Self {
+            typ: FindingType::ParseError,
+            file_name: Some(error.file_name.clone()),
+            line_number: error.line_number,
+            name: error.line.clone(),
+            detail: format!("expected {}", error.expected),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = match (&self.file_name, self.line_number) {
+            (Some(file_name), Some(line_number)) => format!("{}, line {}: ", file_name, line_number),
+            (Some(file_name), None) => format!("{}: ", file_name),
+            _ => "".to_string(),
+        };
+        write!(f, "{}{}: \"{}\": {}.", location, self.typ.variant_to_string(), self.name, self.detail)
+    }
+}
+
+impl FindingType {
+    pub fn variant_to_string(&self) -> &str {
+        match self {
+            FindingType::ParseError => "ParseError",
+            FindingType::DanglingReference => "DanglingReference",
+            FindingType::Duplicate => "Duplicate",
+            FindingType::ExtraSpaces => "ExtraSpaces",
+            FindingType::ShrineWithoutChallenge => "ShrineWithoutChallenge",
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use std::fs::File;
-use std::io::{prelude::*, BufReader};
+use crate::model_3::data_source::DataSource;
+use crate::model_3::model::{ContentOptions, Model};
+use crate::model_3::parse;
 
 pub fn main() {
     parse_locations();
@@ -7,17 +8,31 @@ pub fn main() {
 
 fn parse_locations() {
     // This uses the JS script from https://www.reddit.com/r/zelda/comments/60z2ho/botw_i_datamined_a_map_of_all_226_discoverable/
-    // A typical line is:
-    //   {"internal_name":"Location_DeathMountain_Entrance", "display_name":"Maw of Death Mountain", "x":2402.58, "y":-1320.01},
-    let file_name = "Breath of the Wild Map Locations for Completion Raw.txt";
-    let file = File::open(file_name).unwrap();
-    for line in BufReader::new(file).lines() {
-        let line = line.unwrap();
-        if line.starts_with("{\"internal_name\"") {
-            let location_name = util::parse::between(&line, "\"display_name\":\"", "\", \"x\"").trim();
-            println!("{}", location_name);
+    // The coordinates themselves are loaded into the model by model_3::parse::load_map_locations().
+    let model = match Model::load_text(&DataSource::new_default(), &ContentOptions::default()) {
+        Ok(model) => model,
+        Err(errors) => {
+            parse::print_errors(&errors);
+            return;
         }
+    };
+    let map_match = match &model.map_location_match {
+        Some(map_match) => map_match,
+        None => {
+            println!("No map file matches were kept when the catalog was loaded.");
+            return;
+        }
+    };
+    println!("Map locations not in the catalog ({}):", map_match.unmatched_map_names.len());
+    for name in map_match.unmatched_map_names.iter() {
+        println!("\t{}", name);
+    }
+    println!("Map locations listed more than once ({}):", map_match.duplicate_map_names.len());
+    for name in map_match.duplicate_map_names.iter() {
+        println!("\t{}", name);
+    }
+    println!("Catalog locations not in the map ({}):", map_match.unmatched_location_names.len());
+    for name in map_match.unmatched_location_names.iter() {
+        println!("\t{}", name);
     }
 }
-
-
@@ -1,18 +1,22 @@
-use std::fs::File;
-use std::io;
-use std::io::BufRead;
 use util::tab::*;
 use super::*;
+use crate::model_3::data_source::DataSource;
+use crate::model_3::parse::{self, ParseError};
 
+// Used when the BOTW_DATA_PATH environment variable isn't set.
 #[allow(dead_code)]
 const PATH_DATA: &str = r"T:\Docs\Games\Breath of the Wild";
+#[allow(dead_code)]
+const FILE_NAME_SESSIONS: &str = "Sess.txt";
 
 pub fn main() {
-    // let game = parse_game();
+    // if let Err(errors) = parse_game(&DataSource::from_env_or_directory(PATH_DATA)) {
+    //     parse::print_errors(&errors);
+    // }
 }
 
 #[allow(dead_code)]
-fn parse_game() -> Game {
+fn parse_game(source: &DataSource) -> Result<Game, Vec<ParseError>> {
 
     /*
     let val_trim = "1".replace("\"", "").replace(",", "").trim().to_string();
@@ -23,21 +27,20 @@ fn parse_game() -> Game {
     */
 
     let mut game = Game::new();
-    parse_sessions(&mut game);
+    parse_sessions(&mut game, source)?;
 
 
 
 
     game.report_sessions();
-    game
+    Ok(game)
 }
 
 #[allow(dead_code)]
-fn parse_sessions(game: &mut Game) {
-    let file = File::open(format!("{}/{}", PATH_DATA, "Sess.txt")).unwrap();
-    for (line_index, raw_line_result) in io::BufReader::new(file).lines().enumerate() {
+fn parse_sessions(game: &mut Game, source: &DataSource) -> Result<(), Vec<ParseError>> {
+    let content = parse::read_file(source, FILE_NAME_SESSIONS)?;
+    for (line_index, line) in content.lines().enumerate() {
         if line_index > 0 {
-            let line = raw_line_result.unwrap();
             let cells = line.split("\t").collect::<Vec<_>>();
             let session_number = cell_as_usize(cells[0]);
             if session_number == 0 {
@@ -47,5 +50,6 @@ fn parse_sessions(game: &mut Game) {
             game.sessions.insert(session_number, seconds);
         }
     }
+    Ok(())
 }
 